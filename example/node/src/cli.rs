@@ -84,9 +84,35 @@ pub struct Cli {
 	/// Defines the 'baseline' balance in case the node is a collator.
 	///
 	/// A warning will be logged when the free relay chain balance of the collator drops below this
-	/// value.
+	/// value. Orders that would drop the balance below this value are not placed, and the next
+	/// authority able to afford the order becomes responsible for placing it.
 	#[arg(long, default_value = "1000000000000")]
 	pub on_demand_baseline_balance: Balance,
+
+	/// Place on-demand orders with `place_order_allow_death` instead of `place_order_keep_alive`.
+	///
+	/// This allows the relay chain account of the collator to be reaped by its own order.
+	#[arg(long)]
+	pub on_demand_allow_death: bool,
+
+	/// The maximum amount the collator is allowed to spend on on-demand orders within
+	/// `--on-demand-spend-window` relay chain blocks.
+	///
+	/// If not set, there is no limit on the amount spent.
+	#[arg(long)]
+	pub on_demand_max_spend: Option<Balance>,
+
+	/// The length of the on-demand spend budget window in relay chain blocks.
+	#[arg(long, default_value = "600")]
+	pub on_demand_spend_window: u32,
+
+	/// The number of relay chain blocks without a new parachain block after which placing an
+	/// order becomes the responsibility of the next authority.
+	///
+	/// Once every authority had its turn, any authority can place the order. If not set, only the
+	/// elected collator places orders.
+	#[arg(long)]
+	pub on_demand_fallback_after: Option<u32>,
 }
 
 #[derive(Debug)]
@@ -2,6 +2,7 @@ use cumulus_client_service::storage_proof_size::HostFunctions as ReclaimHostFunc
 use cumulus_primitives_core::ParaId;
 use frame_benchmarking_cli::{BenchmarkCmd, SUBSTRATE_REFERENCE_HARDWARE};
 use log::info;
+use on_demand_service::{budget::SpendBudget, fallback::PlacerFallback};
 use parachain_example_runtime::Block;
 use sc_cli::{
 	ChainSpec, CliConfiguration, DefaultConfigurationValues, ImportParams, KeystoreParams,
@@ -246,6 +247,12 @@ pub fn run() -> Result<()> {
 				info!("Is collating: {}", if config.role.is_authority() { "yes" } else { "no" });
 
 				let baseline_balance = cli.on_demand_baseline_balance;
+				let spend_budget = cli
+					.on_demand_max_spend
+					.map(|max_spend| SpendBudget { max_spend, window: cli.on_demand_spend_window });
+				let placer_fallback = cli
+					.on_demand_fallback_after
+					.map(|rotate_after| PlacerFallback { rotate_after });
 				crate::service::start_parachain_node(
 					config,
 					polkadot_config,
@@ -253,6 +260,9 @@ pub fn run() -> Result<()> {
 					id,
 					hwbench,
 					baseline_balance,
+					cli.on_demand_allow_death,
+					spend_budget,
+					placer_fallback,
 				)
 				.await
 				.map(|r| r.0)
@@ -26,29 +26,30 @@ use cumulus_primitives_core::{
 	relay_chain::{CollatorPair, ValidationCode},
 	ParaId,
 };
-use cumulus_relay_chain_interface::{BlockNumber, OverseerHandle, RelayChainInterface};
+use cumulus_relay_chain_interface::{OverseerHandle, RelayChainInterface};
 
 // Substrate Imports
-use codec::Encode;
 use frame_benchmarking_cli::SUBSTRATE_REFERENCE_HARDWARE;
-use on_demand_primitives::OnDemandRuntimeApi;
-use pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi;
 use polkadot_primitives::Balance;
 use prometheus_endpoint::Registry;
-use sc_client_api::{Backend, UsageProvider};
+use sc_client_api::Backend;
 use sc_consensus::ImportQueue;
 use sc_executor::{HeapAllocStrategy, WasmExecutor, DEFAULT_HEAP_ALLOC_STRATEGY};
 use sc_network::NetworkBlock;
 use sc_service::{Configuration, PartialComponents, TFullBackend, TFullClient, TaskManager};
 use sc_telemetry::{Telemetry, TelemetryHandle, TelemetryWorker, TelemetryWorkerHandle};
-use sc_transaction_pool_api::{OffchainTransactionPoolFactory, TransactionPool};
-use sp_api::ProvideRuntimeApi;
+use sc_transaction_pool_api::OffchainTransactionPoolFactory;
 use sp_consensus_aura::sr25519::AuthorityPair;
 use sp_keystore::KeystorePtr;
-use on_demand_service::config::OnDemandAura;
 
 // RegionX Modules
-use on_demand_service::{config::OrderCriteria, start_on_demand};
+use on_demand_service::{
+	budget::SpendBudget,
+	config::OnDemandAura,
+	criteria::{FeeThreshold, InboundMessages, Or},
+	fallback::PlacerFallback,
+	start_on_demand,
+};
 
 #[docify::export(wasm_executor)]
 type ParachainExecutor = WasmExecutor<ParachainHostFunctions>;
@@ -73,6 +74,7 @@ type OnDemandConfig = OnDemandAura<
 	Arc<dyn RelayChainInterface>,
 	ParachainClient,
 	Block,
+	ParachainBackend,
 	AuthorityPair,
 	sc_transaction_pool::FullPool<Block, ParachainClient>,
 	Balance,
@@ -80,51 +82,21 @@ type OnDemandConfig = OnDemandAura<
 	Balance,
 >;
 
-// https://github.com/paritytech/cumulus/issues/2154
-pub struct OrderPlacementCriteria;
-impl OrderCriteria for OrderPlacementCriteria {
-	type Block = Block;
-	type P = ParachainClient;
-	type ExPool = sc_transaction_pool::FullPool<Block, ParachainClient>;
-
-	// Checks if the fee threshold has been reached.
-	fn should_place_order(
-		parachain: &Self::P,
-		transaction_pool: Arc<Self::ExPool>,
-		_height: BlockNumber,
-	) -> bool {
-		let pending_iterator = transaction_pool.ready();
-		let block_hash = parachain.usage_info().chain.best_hash;
-		let mut total_fees = Balance::from(0u32);
-
-		for pending_tx in pending_iterator {
-			let pending_tx_data = pending_tx.data.clone();
-			let utx_length = pending_tx_data.encode().len() as u32;
-
-			let fee_details =
-				parachain
-					.runtime_api()
-					.query_fee_details(block_hash, pending_tx_data, utx_length);
-
-			if let Ok(details) = fee_details {
-				total_fees = total_fees.saturating_add(details.final_fee());
-			}
-		}
-
-		let Some(fee_threshold) = parachain.runtime_api().threshold_parameter(block_hash).ok()
-		else {
-			return false;
-		};
-
-		if fee_threshold > 0 {
-			log::info!(
-				"{}% of the threshold requirement met",
-				total_fees.saturating_div(fee_threshold).saturating_mul(100)
-			);
-		}
-		total_fees >= fee_threshold
-	}
-}
+type OrderPlacementCriteria = Or<
+	FeeThreshold<
+		Block,
+		ParachainClient,
+		sc_transaction_pool::FullPool<Block, ParachainClient>,
+		Balance,
+	>,
+	InboundMessages<
+		Block,
+		ParachainClient,
+		sc_transaction_pool::FullPool<Block, ParachainClient>,
+		Balance,
+		Balance,
+	>,
+>;
 
 /// Starts a `ServiceBuilder` for a full service.
 ///
@@ -297,6 +269,9 @@ pub async fn start_parachain_node(
 	para_id: ParaId,
 	hwbench: Option<sc_sysinfo::HwBench>,
 	on_demand_baseline_balance: Balance,
+	on_demand_allow_death: bool,
+	on_demand_spend_budget: Option<SpendBudget>,
+	on_demand_placer_fallback: Option<PlacerFallback>,
 ) -> sc_service::error::Result<(TaskManager, Arc<ParachainClient>)> {
 	let parachain_config = prepare_node_config(parachain_config);
 
@@ -461,7 +436,11 @@ pub async fn start_parachain_node(
 			params.keystore_container.keystore(),
 			relay_rpc,
 			on_demand_baseline_balance,
+			on_demand_allow_death,
 			relay_chain_slot_duration,
+			on_demand_spend_budget,
+			on_demand_placer_fallback,
+			prometheus_registry.as_ref(),
 		)?;
 		start_consensus(
 			client.clone(),
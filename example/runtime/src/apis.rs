@@ -44,7 +44,7 @@ use sp_version::RuntimeVersion;
 use super::{
 	AccountId, Balance, Block, BlockNumber, ConsensusHook, Executive, InherentDataExt, Nonce,
 	OnDemand, ParachainSystem, Runtime, RuntimeCall, RuntimeGenesisConfig, SessionKeys, System,
-	ThresholdParameter, TransactionPayment, SLOT_DURATION, VERSION,
+	ThresholdParameter, TransactionPayment, UncheckedExtrinsic, SLOT_DURATION, VERSION,
 };
 
 // we move some impls outside so we can easily use them with `docify`.
@@ -216,6 +216,7 @@ impl_runtime_apis! {
 		}
 	}
 
+	#[api_version(9)]
 	impl on_demand_primitives::OnDemandRuntimeApi<Block, Balance, BlockNumber, ThresholdParameter> for Runtime {
 		fn slot_width() -> u32 {
 			OnDemand::slot_width()
@@ -223,6 +224,35 @@ impl_runtime_apis! {
 		fn threshold_parameter() -> ThresholdParameter {
 			OnDemand::threshold_parameter()
 		}
+		fn max_order_price() -> Option<Balance> {
+			OnDemand::max_order_price()
+		}
+		fn max_idle_relay_blocks() -> Option<BlockNumber> {
+			OnDemand::max_idle_relay_blocks()
+		}
+		fn inbound_message_threshold() -> Option<on_demand_primitives::MessageThreshold<BlockNumber>> {
+			OnDemand::inbound_message_threshold()
+		}
+		fn thresholds() -> Vec<(on_demand_primitives::ThresholdKind, ThresholdParameter)> {
+			OnDemand::thresholds().into_iter().collect()
+		}
+		fn spot_price_report(price: Balance) -> Option<<Block as BlockT>::Extrinsic> {
+			let call = OnDemand::signed_spot_price_report(price)?;
+			Some(UncheckedExtrinsic::new_unsigned(call.into()))
+		}
+		fn on_demand_config() -> on_demand_primitives::OnDemandSettings<Balance, BlockNumber, ThresholdParameter> {
+			on_demand_primitives::OnDemandSettings {
+				slot_width: OnDemand::slot_width(),
+				threshold_parameter: OnDemand::threshold_parameter(),
+				max_order_price: OnDemand::max_order_price(),
+				max_idle_relay_blocks: OnDemand::max_idle_relay_blocks(),
+				inbound_message_threshold: OnDemand::inbound_message_threshold(),
+				thresholds: OnDemand::thresholds().into_iter().collect(),
+			}
+		}
+		fn payment_account(authority: Vec<u8>) -> Option<sp_runtime::AccountId32> {
+			OnDemand::payer_of(&AuraId::from_slice(&authority).ok()?)
+		}
 	}
 
 	#[cfg(feature = "try-runtime")]
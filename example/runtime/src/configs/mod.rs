@@ -23,17 +23,21 @@
 //
 // For more information, please refer to <http://unlicense.org>
 
+mod relay_events;
 mod xcm_config;
 
 // Substrate and Polkadot dependencies
-use cumulus_pallet_parachain_system::RelayNumberMonotonicallyIncreases;
-use cumulus_primitives_core::{AggregateMessageOrigin, ParaId};
+use cumulus_pallet_parachain_system::{
+	RelayNumberMonotonicallyIncreases, RelaychainDataProvider, RelaychainStateProvider,
+};
+use cumulus_primitives_core::{relay_chain, AggregateMessageOrigin, ParaId};
 use frame_support::{
 	derive_impl,
 	dispatch::DispatchClass,
 	parameter_types,
 	traits::{
-		ConstBool, ConstU32, ConstU64, ConstU8, EitherOfDiverse, TransformOrigin, VariantCountOf,
+		tokens::imbalance::ResolveTo, ConstBool, ConstU32, ConstU64, ConstU8, Contains,
+		EitherOfDiverse, Get, TransformOrigin, VariantCountOf,
 	},
 	weights::{ConstantMultiplier, Weight},
 	PalletId,
@@ -42,25 +46,30 @@ use frame_system::{
 	limits::{BlockLength, BlockWeights},
 	EnsureRoot,
 };
+use on_demand_primitives::ThresholdKind;
 use pallet_xcm::{EnsureXcm, IsVoiceOfBody};
 use parachains_common::message_queue::{NarrowOriginToSibling, ParaIdToSibling};
 use polkadot_runtime_common::{
 	xcm_sender::NoPriceForMessageDelivery, BlockHashCount, SlowAdjustingFeeUpdate,
 };
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
-use sp_runtime::Perbill;
+use sp_runtime::{
+	traits::{AccountIdConversion, BlockNumberProvider, Convert, MaybeConvert},
+	transaction_validity::TransactionPriority,
+	FixedPointNumber, FixedU128, Perbill,
+};
 use sp_version::RuntimeVersion;
 use xcm::latest::prelude::BodyId;
 
 // Local module imports
 use super::{
-	weights::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight},
-	AccountId, Aura, Balance, Balances, Block, BlockNumber, CollatorSelection, ConsensusHook, Hash,
-	MessageQueue, Nonce, PalletInfo, ParachainSystem, Runtime, RuntimeCall, RuntimeEvent,
-	RuntimeFreezeReason, RuntimeHoldReason, RuntimeOrigin, RuntimeTask, Session, SessionKeys,
-	System, ThresholdParameter, WeightToFee, XcmpQueue, AVERAGE_ON_INITIALIZE_RATIO,
-	EXISTENTIAL_DEPOSIT, HOURS, MAXIMUM_BLOCK_WEIGHT, MICRO_UNIT, NORMAL_DISPATCH_RATIO,
-	SLOT_DURATION, VERSION,
+	weights::{self, BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight},
+	AccountId, Aura, Authorship, Balance, Balances, Block, BlockNumber, CollatorSelection,
+	ConsensusHook, Hash, MessageQueue, Nonce, PalletInfo, ParachainInfo, ParachainSystem, Runtime,
+	RuntimeCall, RuntimeEvent, RuntimeFreezeReason, RuntimeHoldReason, RuntimeOrigin, RuntimeTask,
+	Session, SessionKeys, System, ThresholdParameter, WeightToFee, XcmpQueue,
+	AVERAGE_ON_INITIALIZE_RATIO, EXISTENTIAL_DEPOSIT, HOURS, MAXIMUM_BLOCK_WEIGHT, MICRO_UNIT,
+	NORMAL_DISPATCH_RATIO, SLOT_DURATION, VERSION,
 };
 use xcm_config::{RelayLocation, XcmOriginToTransactDispatchOrigin};
 
@@ -164,11 +173,16 @@ impl pallet_balances::Config for Runtime {
 parameter_types! {
 	/// Relay Chain `TransactionByteFee` / 10
 	pub const TransactionByteFee: Balance = 10 * MICRO_UNIT;
+	/// The collator staking pot receives the fees not moved into the reimbursement pot.
+	pub StakingPotAccount: AccountId = PotId::get().into_account_truncating();
 }
 
 impl pallet_transaction_payment::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
-	type OnChargeTransaction = pallet_transaction_payment::FungibleAdapter<Balances, ()>;
+	type OnChargeTransaction = pallet_transaction_payment::FungibleAdapter<
+		Balances,
+		pallet_on_demand::FeeShare<Runtime, ResolveTo<StakingPotAccount, Balances>>,
+	>;
 	type WeightToFee = WeightToFee;
 	type LengthToFee = ConstantMultiplier<Balance, TransactionByteFee>;
 	type FeeMultiplierUpdate = SlowAdjustingFeeUpdate<Self>;
@@ -307,18 +321,155 @@ impl pallet_collator_selection::Config for Runtime {
 #[cfg(feature = "runtime-benchmarks")]
 pub struct BenchHelper;
 #[cfg(feature = "runtime-benchmarks")]
-impl pallet_on_demand::BenchmarkHelper<Balance> for BenchHelper {
-	fn mock_threshold_parameter() -> Balance {
-		1_000u32.into()
+impl pallet_on_demand::BenchmarkHelper<Balance, AccountId, AuraId> for BenchHelper {
+	fn max_threshold_parameter() -> Balance {
+		Balance::MAX
+	}
+
+	fn set_block_author(author: AccountId) {
+		use codec::Encode;
+
+		// Aura elects the authority at index `slot % authorities` of slot 0, i.e. the first one,
+		// which `pallet-authorship` maps to the first session validator.
+		if pallet_aura::Authorities::<Runtime>::get().is_empty() {
+			Self::add_authority(<AuraId as sp_runtime::RuntimeAppPublic>::generate_pair(None));
+		}
+		let mut validators = Session::validators();
+		validators.insert(0, author);
+		pallet_session::Validators::<Runtime>::put(validators);
+		System::deposit_log(sp_runtime::DigestItem::PreRuntime(
+			sp_consensus_aura::AURA_ENGINE_ID,
+			sp_consensus_aura::Slot::from(0).encode(),
+		));
+	}
+
+	fn add_authority(authority: AuraId) {
+		let mut authorities = pallet_aura::Authorities::<Runtime>::get();
+		authorities
+			.try_push(authority)
+			.expect("The example runtime allows 100_000 authorities");
+		Aura::change_authorities(authorities);
+	}
+
+	fn set_authority_key(who: AccountId, authority: AuraId) {
+		pallet_session::NextKeys::<Runtime>::insert(who, SessionKeys { aura: authority });
+	}
+}
+
+parameter_types! {
+	pub const FeeThresholdKind: ThresholdKind = ThresholdKind::Fees;
+	pub const OnDemandUnsignedPriority: TransactionPriority = TransactionPriority::MAX / 2;
+	pub const OnDemandPalletId: PalletId = PalletId(*b"py/ondmd");
+	/// The price of one relay chain token in native tokens, adjustable through `set_storage`.
+	pub storage RelayTokenPrice: FixedU128 = FixedU128::one();
+}
+
+/// Converts an amount of relay chain tokens into native tokens at the `RelayTokenPrice`.
+pub struct RelayToNative;
+impl Convert<Balance, Balance> for RelayToNative {
+	fn convert(amount: Balance) -> Balance {
+		RelayTokenPrice::get().saturating_mul_int(amount)
+	}
+}
+
+/// Provides the author of the current block tracked by pallet-authorship.
+pub struct BlockAuthor;
+impl Get<Option<AccountId>> for BlockAuthor {
+	fn get() -> Option<AccountId> {
+		Authorship::author()
+	}
+}
+
+/// Checks whether a key belongs to the current Aura authorities.
+pub struct AuraAuthorities;
+impl Contains<AuraId> for AuraAuthorities {
+	fn contains(authority: &AuraId) -> bool {
+		pallet_aura::Authorities::<Runtime>::get().contains(authority)
+	}
+}
+
+/// Returns the Aura key in the session keys of a collator.
+pub struct AuraKeyOf;
+impl MaybeConvert<AccountId, AuraId> for AuraKeyOf {
+	fn maybe_convert(who: AccountId) -> Option<AuraId> {
+		pallet_session::NextKeys::<Runtime>::get(who).map(|keys| keys.aura)
+	}
+}
+
+/// Provides the relay parent state root and block number tracked by parachain-system.
+pub struct RelayParentState;
+impl pallet_on_demand::RelayParentStateRoot for RelayParentState {
+	fn state_root() -> relay_chain::Hash {
+		RelaychainDataProvider::<Runtime>::current_relay_chain_state().state_root
+	}
+
+	fn block_number() -> relay_chain::BlockNumber {
+		RelaychainDataProvider::<Runtime>::current_block_number()
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn set_state_root(root: relay_chain::Hash) {
+		let mut state = RelaychainDataProvider::<Runtime>::current_relay_chain_state();
+		state.state_root = root;
+		RelaychainDataProvider::<Runtime>::set_current_relay_chain_state(state);
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn set_block_number(number: relay_chain::BlockNumber) {
+		RelaychainDataProvider::<Runtime>::set_block_number(number);
 	}
 }
 
 impl pallet_on_demand::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
 	type AdminOrigin = EnsureRoot<AccountId>;
+	type Balance = Balance;
 	type BlockNumber = BlockNumber;
 	type ThresholdParameter = ThresholdParameter; // Represents fee threshold.
+	type ThresholdParameterKind = FeeThresholdKind;
+	type MaxThresholds = ConstU32<8>;
+	type MaxScheduledPerBlock = ConstU32<16>;
+	type MaxRelayScheduled = ConstU32<16>;
+	type MaxProfileEntries = ConstU32<24>;
+	type UnixTime = Timestamp;
+	// Spot prices are in relay chain tokens, whereas the fee threshold is in native tokens.
+	type PriceToThreshold = RelayToNative;
+	type AuthorityId = AuraId;
+	type Authorities = AuraAuthorities;
+	type AuthorityOf = AuraKeyOf;
+	type UnsignedPriority = OnDemandUnsignedPriority;
+	type SelfParaId = ParachainInfo;
+	type RelayEvents = relay_events::RococoEvents;
+	type RelayState = RelayParentState;
+	// The relay chain keeps the block hashes of the last 4096 blocks.
+	type OrderRetention = ConstU32<4096>;
+	type MaxOrderBlocks = ConstU32<1024>;
+	type Currency = Balances;
+	type PalletId = OnDemandPalletId;
+	type OrderCostToBalance = RelayToNative;
+	type BlockAuthor = BlockAuthor;
 	#[cfg(feature = "runtime-benchmarks")]
 	type BenchmarkHelper = BenchHelper;
-	type WeightInfo = ();
+	type WeightInfo = weights::pallet_on_demand::WeightInfo<Runtime>;
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn spot_price_and_order_cost_convert_at_relay_token_price() {
+		sp_io::TestExternalities::default().execute_with(|| {
+			RelayTokenPrice::set(&FixedU128::from_rational(5, 2));
+
+			assert_eq!(
+				<Runtime as pallet_on_demand::Config>::PriceToThreshold::convert(1_000),
+				2_500
+			);
+			assert_eq!(
+				<Runtime as pallet_on_demand::Config>::OrderCostToBalance::convert(1_000),
+				2_500
+			);
+		});
+	}
 }
@@ -0,0 +1,199 @@
+//! The rococo relay chain events needed for verifying on-demand orders.
+//!
+//! The relay chain runtime can't be linked into the parachain runtime, so its `RuntimeEvent` is
+//! mirrored here, following the layout of the relay chain runtime rather than the types of the
+//! parachain runtime.
+//!
+//! SCALE encoded events carry no length, so the records of a block are decoded one after another
+//! until the order is found. Only the pallets emitting events in blocks with on-demand orders are
+//! covered: an order can't be proven if an event of any other pallet, or of a changed event
+//! layout, precedes it in its block. Events following the order are never decoded.
+//! The mirror has to be kept in sync with the relay chain runtime upgrades.
+
+use codec::{Decode, Encode};
+use cumulus_primitives_core::{
+	relay_chain::{CandidateReceipt, CoreIndex, GroupIndex, Hash, HeadData},
+	ParaId,
+};
+use frame_support::{
+	dispatch::{DispatchClass, Pays},
+	weights::Weight,
+};
+use sp_runtime::{AccountId32, DispatchError};
+
+/// The events of the rococo relay chain runtime.
+#[derive(Encode, Decode)]
+pub enum RelayEvent {
+	#[codec(index = 0)]
+	System(SystemEvent),
+	#[codec(index = 4)]
+	Balances(pallet_balances::Event<crate::Runtime>),
+	#[codec(index = 33)]
+	TransactionPayment(pallet_transaction_payment::Event<crate::Runtime>),
+	#[codec(index = 53)]
+	ParaInclusion(ParaInclusionEvent),
+	#[codec(index = 64)]
+	MessageQueue(MessageQueueEvent),
+	#[codec(index = 66)]
+	OnDemandAssignmentProvider(OnDemandEvent),
+}
+
+/// The dispatch info of the relay chain `frame_system` events.
+#[derive(Encode, Decode)]
+pub struct DispatchInfo {
+	pub weight: Weight,
+	pub class: DispatchClass,
+	pub pays_fee: Pays,
+}
+
+/// The events of the relay chain `frame_system` pallet.
+#[derive(Encode, Decode)]
+pub enum SystemEvent {
+	ExtrinsicSuccess { dispatch_info: DispatchInfo },
+	ExtrinsicFailed { dispatch_error: DispatchError, dispatch_info: DispatchInfo },
+	CodeUpdated,
+	NewAccount { account: AccountId32 },
+	KilledAccount { account: AccountId32 },
+	Remarked { sender: AccountId32, hash: Hash },
+	UpgradeAuthorized { code_hash: Hash, check_version: bool },
+}
+
+/// The events of the relay chain `parachains_inclusion` pallet.
+#[derive(Encode, Decode)]
+pub enum ParaInclusionEvent {
+	CandidateBacked(CandidateReceipt<Hash>, HeadData, CoreIndex, GroupIndex),
+	CandidateIncluded(CandidateReceipt<Hash>, HeadData, CoreIndex, GroupIndex),
+	CandidateTimedOut(CandidateReceipt<Hash>, HeadData, CoreIndex),
+	UpwardMessagesReceived { from: ParaId, count: u32 },
+}
+
+/// The queue of upward messages sent by a parachain.
+#[derive(Encode, Decode)]
+pub enum UmpQueueId {
+	Para(ParaId),
+}
+
+/// The origin of the messages processed by the relay chain `MessageQueue` pallet.
+#[derive(Encode, Decode)]
+pub enum AggregateMessageOrigin {
+	Ump(UmpQueueId),
+}
+
+/// The reason the relay chain `MessageQueue` pallet failed to process a message.
+#[derive(Encode, Decode)]
+pub enum ProcessMessageError {
+	BadFormat,
+	Corrupt,
+	Unsupported,
+	Overweight(Weight),
+	Yield,
+	StackLimitReached,
+}
+
+/// The events of the relay chain `MessageQueue` pallet.
+#[derive(Encode, Decode)]
+pub enum MessageQueueEvent {
+	ProcessingFailed {
+		id: Hash,
+		origin: AggregateMessageOrigin,
+		error: ProcessMessageError,
+	},
+	Processed {
+		id: Hash,
+		origin: AggregateMessageOrigin,
+		weight_used: Weight,
+		success: bool,
+	},
+	OverweightEnqueued {
+		id: [u8; 32],
+		origin: AggregateMessageOrigin,
+		page_index: u32,
+		message_index: u32,
+	},
+	PageReaped {
+		origin: AggregateMessageOrigin,
+		index: u32,
+	},
+}
+
+/// The events of the relay chain `parachains_on_demand` pallet.
+#[derive(Encode, Decode)]
+pub enum OnDemandEvent {
+	OnDemandOrderPlaced { para_id: ParaId, spot_price: u128, ordered_by: AccountId32 },
+	SpotPriceSet { spot_price: u128 },
+}
+
+/// Finds the on-demand orders within the rococo relay chain events.
+pub struct RococoEvents;
+impl pallet_on_demand::RelayEvents for RococoEvents {
+	type Event = RelayEvent;
+
+	fn order_placed(event: &RelayEvent) -> Option<(ParaId, u128, AccountId32)> {
+		match event {
+			RelayEvent::OnDemandAssignmentProvider(OnDemandEvent::OnDemandOrderPlaced {
+				para_id,
+				spot_price,
+				ordered_by,
+			}) => Some((*para_id, *spot_price, ordered_by.clone())),
+			_ => None,
+		}
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn order_placed_event(para_id: ParaId, spot_price: u128, payer: AccountId32) -> RelayEvent {
+		RelayEvent::OnDemandAssignmentProvider(OnDemandEvent::OnDemandOrderPlaced {
+			para_id,
+			spot_price,
+			ordered_by: payer,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_system::Phase;
+	use pallet_on_demand::proof::{contains_order, RelayEventRecord};
+
+	fn record(phase: Phase, event: RelayEvent) -> RelayEventRecord<RelayEvent> {
+		RelayEventRecord { phase, event, topics: vec![] }
+	}
+
+	#[test]
+	fn orders_after_relay_system_and_message_queue_events_are_found() {
+		let payer = AccountId32::new([1; 32]);
+		let para_id = ParaId::new(2000);
+		let dispatch_info = DispatchInfo {
+			weight: Weight::zero(),
+			class: DispatchClass::Normal,
+			pays_fee: Pays::Yes,
+		};
+		let events = vec![
+			record(
+				Phase::Initialization,
+				RelayEvent::MessageQueue(MessageQueueEvent::Processed {
+					id: Hash::repeat_byte(2),
+					origin: AggregateMessageOrigin::Ump(UmpQueueId::Para(ParaId::new(1000))),
+					weight_used: Weight::from_parts(1_000, 10),
+					success: true,
+				}),
+			),
+			record(
+				Phase::ApplyExtrinsic(0),
+				RelayEvent::System(SystemEvent::ExtrinsicSuccess { dispatch_info }),
+			),
+			record(
+				Phase::ApplyExtrinsic(1),
+				RelayEvent::OnDemandAssignmentProvider(OnDemandEvent::OnDemandOrderPlaced {
+					para_id,
+					spot_price: 100,
+					ordered_by: payer.clone(),
+				}),
+			),
+		]
+		.encode();
+
+		assert!(contains_order::<RococoEvents>(&events, 1, para_id, 100, &payer));
+		assert!(!contains_order::<RococoEvents>(&events, 1, para_id, 99, &payer));
+	}
+}
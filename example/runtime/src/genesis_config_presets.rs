@@ -1,14 +1,16 @@
 use cumulus_primitives_core::ParaId;
 
 use crate::{
-	AccountId, BalancesConfig, CollatorSelectionConfig, ParachainInfoConfig, PolkadotXcmConfig,
-	RuntimeGenesisConfig, SessionConfig, SessionKeys, SudoConfig, EXISTENTIAL_DEPOSIT,
+	AccountId, BalancesConfig, CollatorSelectionConfig, OnDemand, OnDemandConfig,
+	ParachainInfoConfig, PolkadotXcmConfig, RuntimeGenesisConfig, SessionConfig, SessionKeys,
+	SudoConfig, EXISTENTIAL_DEPOSIT,
 };
 use alloc::{vec, vec::Vec};
 use parachains_common::{genesis_config_helpers::*, AuraId};
 use serde_json::Value;
 use sp_core::sr25519;
 use sp_genesis_builder::PresetId;
+use sp_runtime::Perbill;
 
 /// The default XCM version to set in genesis config.
 const SAFE_XCM_VERSION: u32 = xcm::prelude::XCM_VERSION;
@@ -31,6 +33,8 @@ fn testnet_genesis(
 			balances: endowed_accounts
 				.iter()
 				.cloned()
+				// Fund the reimbursement pot, so collators get reimbursed from the start.
+				.chain([OnDemand::pot()])
 				.map(|k| (k, 1u128 << 60))
 				.collect::<Vec<_>>(),
 		},
@@ -58,6 +62,10 @@ fn testnet_genesis(
 			..Default::default()
 		},
 		sudo: SudoConfig { key: Some(root) },
+		on_demand: OnDemandConfig {
+			reimbursement_share: Perbill::from_percent(20),
+			..Default::default()
+		},
 		..Default::default()
 	};
 
@@ -19,6 +19,7 @@
 
 pub mod block_weights;
 pub mod extrinsic_weights;
+pub mod pallet_on_demand;
 pub mod paritydb_weights;
 pub mod rocksdb_weights;
 
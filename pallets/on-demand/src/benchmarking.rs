@@ -2,9 +2,18 @@
 
 use super::*;
 
-pub trait BenchmarkHelper<ThresholdParameter> {
-	// Return a mock threshold parameter that is not the default value.
-	fn mock_threshold_parameter() -> ThresholdParameter;
+pub trait BenchmarkHelper<ThresholdParameter, AccountId, AuthorityId> {
+	// Return the threshold parameter with the largest encoding, which is not the default value.
+	fn max_threshold_parameter() -> ThresholdParameter;
+
+	// Make `author` the author of the current block.
+	fn set_block_author(author: AccountId);
+
+	// Make `authority` one of the current collator authorities.
+	fn add_authority(authority: AuthorityId);
+
+	// Make `authority` the collator authority key of `who`.
+	fn set_authority_key(who: AccountId, authority: AuthorityId);
 }
 
 use frame_benchmarking::v2::*;
@@ -12,10 +21,47 @@ fn assert_last_event<T: Config>(generic_event: <T as Config>::RuntimeEvent) {
 	frame_system::Pallet::<T>::assert_last_event(generic_event.into());
 }
 
+fn assert_has_event<T: Config>(generic_event: <T as Config>::RuntimeEvent) {
+	frame_system::Pallet::<T>::assert_has_event(generic_event.into());
+}
+
+/// Returns the threshold parameter with the largest encoding.
+fn max_threshold_parameter<T: Config>() -> Result<T::ThresholdParameter, BenchmarkError> {
+	let param = T::BenchmarkHelper::max_threshold_parameter();
+	if param.encoded_size() != T::ThresholdParameter::max_encoded_len() {
+		return Err(BenchmarkError::Stop(
+			"The threshold parameter doesn't have the largest encoding",
+		))
+	}
+	Ok(param)
+}
+
+/// Fills up the thresholds with `param`, leaving space for one more.
+fn fill_thresholds<T: Config>(param: &T::ThresholdParameter) -> Result<(), BenchmarkError> {
+	for i in 1..T::MaxThresholds::get() {
+		Pallet::<T>::set_threshold_value(
+			on_demand_primitives::ThresholdKind::Custom(i as u8),
+			Some(param.clone()),
+		)?;
+	}
+	Ok(())
+}
+
 #[benchmarks]
 mod benchmarks {
 	use super::*;
-	use frame_support::traits::EnsureOrigin;
+	use alloc::{boxed::Box, vec, vec::Vec};
+	use cumulus_primitives_core::relay_chain::BlockNumber as RelayBlockNumber;
+	use frame_support::{
+		pallet_prelude::{TransactionSource, ValidateUnsigned},
+		traits::{
+			fungible::{Inspect, Mutate},
+			EnsureOrigin, Hooks, UnfilteredDispatchable,
+		},
+	};
+	use frame_system::RawOrigin;
+	use on_demand_primitives::MessageThreshold;
+	use sp_runtime::{traits::Convert, AccountId32, FixedU128, Perbill, RuntimeAppPublic};
 
 	#[benchmark]
 	fn set_slot_width() -> Result<(), BenchmarkError> {
@@ -34,7 +80,8 @@ mod benchmarks {
 		let origin =
 			T::AdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
 
-		let param = T::BenchmarkHelper::mock_threshold_parameter();
+		let param = max_threshold_parameter::<T>()?;
+		fill_thresholds::<T>(&param)?;
 
 		#[extrinsic_call]
 		_(origin as T::RuntimeOrigin, param.clone());
@@ -43,5 +90,370 @@ mod benchmarks {
 		Ok(())
 	}
 
+	#[benchmark]
+	fn set_max_order_price() -> Result<(), BenchmarkError> {
+		let origin =
+			T::AdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		let price: Option<T::Balance> = Some(1_000u32.into());
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, price);
+
+		assert_last_event::<T>(Event::MaxOrderPriceSet { price }.into());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_max_idle_relay_blocks() -> Result<(), BenchmarkError> {
+		let origin =
+			T::AdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		let blocks: Option<T::BlockNumber> = Some(100u32.into());
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, blocks.clone());
+
+		assert_last_event::<T>(Event::MaxIdleRelayBlocksSet { blocks }.into());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_inbound_message_threshold() -> Result<(), BenchmarkError> {
+		let origin =
+			T::AdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		let threshold = Some(MessageThreshold { count: 10, max_age: 100u32.into() });
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, threshold.clone());
+
+		assert_last_event::<T>(Event::InboundMessageThresholdSet { threshold }.into());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_threshold() -> Result<(), BenchmarkError> {
+		let origin =
+			T::AdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		let param = max_threshold_parameter::<T>()?;
+		fill_thresholds::<T>(&param)?;
+
+		// Setting the threshold parameter emits an extra event.
+		let kind = T::ThresholdParameterKind::get();
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, kind, Some(param.clone()));
+
+		assert_has_event::<T>(Event::ThresholdSet { kind, value: Some(param.clone()) }.into());
+		assert_last_event::<T>(Event::ThresholdParameterSet { parameter: param }.into());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn schedule_change() -> Result<(), BenchmarkError> {
+		let origin =
+			T::AdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		let when = frame_system::Pallet::<T>::block_number() + 10u32.into();
+		let change = ScheduledChange::ThresholdParameter(max_threshold_parameter::<T>()?);
+		// Fill up the changes of the block, leaving space for one more.
+		let changes = vec![change.clone(); T::MaxScheduledPerBlock::get() as usize - 1];
+		ScheduledChanges::<T>::insert(
+			when,
+			ScheduledChangesOf::<T>::try_from(changes).map_err(|_| BenchmarkError::Weightless)?,
+		);
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, when, change.clone());
+
+		assert_last_event::<T>(Event::ChangeScheduled { when, change }.into());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn cancel_scheduled_changes() -> Result<(), BenchmarkError> {
+		let origin =
+			T::AdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		let when = frame_system::Pallet::<T>::block_number() + 10u32.into();
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, when);
+
+		assert_last_event::<T>(Event::ScheduledChangesCancelled { when }.into());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn on_initialize(
+		c: Linear<0, { T::MaxScheduledPerBlock::get() }>,
+	) -> Result<(), BenchmarkError> {
+		let now = frame_system::Pallet::<T>::block_number() + 1u32.into();
+		let param = max_threshold_parameter::<T>()?;
+		fill_thresholds::<T>(&param)?;
+		let change = ScheduledChange::ThresholdParameter(param);
+		let changes = vec![change; c as usize];
+		ScheduledChanges::<T>::insert(
+			now,
+			ScheduledChangesOf::<T>::try_from(changes).map_err(|_| BenchmarkError::Weightless)?,
+		);
+
+		#[block]
+		{
+			Pallet::<T>::on_initialize(now);
+		}
+
+		assert!(ScheduledChanges::<T>::get(now).is_empty());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_adaptive_threshold() -> Result<(), BenchmarkError> {
+		let origin =
+			T::AdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		let config = Some(AdaptiveThreshold {
+			margin: FixedU128::from_rational(3, 2),
+			min: 1_000u32.into(),
+			max: 1_000_000u32.into(),
+		});
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, config.clone());
+
+		assert_last_event::<T>(Event::AdaptiveThresholdSet { config }.into());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn report_spot_price() -> Result<(), BenchmarkError> {
+		AdaptiveThresholdConfig::<T>::set(Some(AdaptiveThreshold {
+			margin: FixedU128::from_rational(3, 2),
+			min: 1_000u32.into(),
+			max: 1_000_000u32.into(),
+		}));
+		fill_thresholds::<T>(&max_threshold_parameter::<T>()?)?;
+		let authority = T::AuthorityId::generate_pair(None);
+		T::BenchmarkHelper::add_authority(authority.clone());
+		let payload = SpotPricePayload {
+			price: 10_000u32.into(),
+			block_number: frame_system::Pallet::<T>::block_number(),
+			authority,
+		};
+		let signature = payload
+			.using_encoded(|encoded| payload.authority.sign(&encoded))
+			.ok_or(BenchmarkError::Stop("Failed to sign the spot price report"))?;
+		let call = Call::<T>::report_spot_price { payload, signature };
+
+		#[block]
+		{
+			Pallet::<T>::validate_unsigned(TransactionSource::InBlock, &call)
+				.map_err(|_| BenchmarkError::Stop("Invalid spot price report"))?;
+			call.dispatch_bypass_filter(RawOrigin::None.into())?;
+		}
+
+		assert_last_event::<T>(
+			Event::ThresholdParameterSet {
+				parameter: T::PriceToThreshold::convert(15_000u32.into()),
+			}
+			.into(),
+		);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn record_order(p: Linear<0, 65_536>) -> Result<(), BenchmarkError> {
+		let payer = AccountId32::new([1; 32]);
+		let amount: T::Balance = 10_000u32.into();
+		let (root, proof) = crate::proof::build_order_proof::<T::RelayEvents, _>(
+			T::SelfParaId::get(),
+			payer.clone(),
+			amount,
+			p as usize,
+		);
+		T::RelayState::set_state_root(root);
+
+		#[extrinsic_call]
+		_(RawOrigin::None, Box::new(proof));
+
+		assert_last_event::<T>(Event::OrderRecorded { payer, amount, relay_block: 1 }.into());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_reimbursement_share() -> Result<(), BenchmarkError> {
+		let origin =
+			T::AdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		let share = Perbill::from_percent(20);
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, share);
+
+		assert_last_event::<T>(Event::ReimbursementShareSet { share }.into());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn claim_reimbursement() -> Result<(), BenchmarkError> {
+		let caller: T::AccountId = whitelisted_caller();
+		let authority = T::AuthorityId::generate_pair(None);
+		T::BenchmarkHelper::add_authority(authority.clone());
+		T::BenchmarkHelper::set_authority_key(caller.clone(), authority.clone());
+		T::BenchmarkHelper::set_block_author(caller.clone());
+
+		let payer = AccountId32::new([1; 32]);
+		PaymentAccounts::<T>::insert(&authority, &payer);
+		let relay_amount: T::Balance = 10_000u32.into();
+		Orders::<T>::insert(1, 1, OrderRecord { payer, amount: relay_amount, reimbursed: false });
+		T::RelayState::set_block_number(1);
+
+		let amount = T::OrderCostToBalance::convert(relay_amount);
+		T::Currency::set_balance(&Pallet::<T>::pot(), amount + T::Currency::minimum_balance());
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), 1, 1);
+
+		assert_last_event::<T>(
+			Event::OrderReimbursed { who: caller, relay_block: 1, amount }.into(),
+		);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn prune_orders(o: Linear<0, { T::MaxOrderBlocks::get() }>) -> Result<(), BenchmarkError> {
+		let payer = AccountId32::new([1; 32]);
+		let blocks: Vec<RelayBlockNumber> = (1..=o).collect();
+		for block in &blocks {
+			Orders::<T>::insert(
+				block,
+				1,
+				OrderRecord { payer: payer.clone(), amount: 10_000u32.into(), reimbursed: true },
+			);
+		}
+		OrderBlocks::<T>::set(blocks.try_into().map_err(|_| BenchmarkError::Weightless)?);
+		T::RelayState::set_block_number(o + T::OrderRetention::get() + 1);
+
+		#[block]
+		{
+			Pallet::<T>::prune_orders();
+		}
+
+		assert!(OrderBlocks::<T>::get().is_empty());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_payment_account() -> Result<(), BenchmarkError> {
+		let caller: T::AccountId = whitelisted_caller();
+		let authority = T::AuthorityId::generate_pair(None);
+		T::BenchmarkHelper::set_authority_key(caller.clone(), authority.clone());
+		let payer = AccountId32::new([1; 32]);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), Some(payer.clone()));
+
+		assert_last_event::<T>(Event::PaymentAccountSet { authority, payer: Some(payer) }.into());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn schedule_relay_change() -> Result<(), BenchmarkError> {
+		let origin =
+			T::AdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		let when = T::RelayState::block_number() + 10;
+		let change = ScheduledChange::ThresholdParameter(max_threshold_parameter::<T>()?);
+		// Fill up the relay chain changes, leaving space for one more.
+		let changes = vec![(when, change.clone()); T::MaxRelayScheduled::get() as usize - 1];
+		RelayScheduledChanges::<T>::set(
+			RelayScheduledChangesOf::<T>::try_from(changes)
+				.map_err(|_| BenchmarkError::Weightless)?,
+		);
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, when, change.clone());
+
+		assert_last_event::<T>(Event::RelayChangeScheduled { when, change }.into());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn cancel_relay_scheduled_changes() -> Result<(), BenchmarkError> {
+		let origin =
+			T::AdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		let when = T::RelayState::block_number() + 10;
+		let change = ScheduledChange::ThresholdParameter(max_threshold_parameter::<T>()?);
+		let changes = vec![(when, change); T::MaxRelayScheduled::get() as usize];
+		RelayScheduledChanges::<T>::set(
+			RelayScheduledChangesOf::<T>::try_from(changes)
+				.map_err(|_| BenchmarkError::Weightless)?,
+		);
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, when);
+
+		assert!(RelayScheduledChanges::<T>::get().is_empty());
+		assert_last_event::<T>(Event::RelayScheduledChangesCancelled { when }.into());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_change_profile() -> Result<(), BenchmarkError> {
+		let origin =
+			T::AdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		let change = ScheduledChange::ThresholdParameter(max_threshold_parameter::<T>()?);
+		let entries = (0..T::MaxProfileEntries::get())
+			.map(|offset| (offset, change.clone()))
+			.collect::<Vec<_>>();
+		let profile = Some(ChangeProfile {
+			period: T::MaxProfileEntries::get(),
+			entries: entries.try_into().map_err(|_| BenchmarkError::Weightless)?,
+		});
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, profile.clone());
+
+		assert_last_event::<T>(Event::ChangeProfileSet { profile }.into());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn on_finalize(
+		r: Linear<0, { T::MaxRelayScheduled::get() }>,
+		p: Linear<0, { T::MaxProfileEntries::get() }>,
+	) -> Result<(), BenchmarkError> {
+		let now = frame_system::Pallet::<T>::block_number();
+		let relay_parent = T::RelayState::block_number();
+		let param = max_threshold_parameter::<T>()?;
+		fill_thresholds::<T>(&param)?;
+		let change = ScheduledChange::ThresholdParameter(param);
+
+		let changes = vec![(relay_parent, change.clone()); r as usize];
+		RelayScheduledChanges::<T>::set(
+			RelayScheduledChangesOf::<T>::try_from(changes)
+				.map_err(|_| BenchmarkError::Weightless)?,
+		);
+		// With a period of a single second, all the entries are applied regardless of the time.
+		let entries = vec![(0, change); p as usize];
+		ChangeProfileConfig::<T>::set(Some(ChangeProfile {
+			period: 1,
+			entries: entries.try_into().map_err(|_| BenchmarkError::Weightless)?,
+		}));
+
+		#[block]
+		{
+			Pallet::<T>::on_finalize(now);
+		}
+
+		assert!(RelayScheduledChanges::<T>::get().is_empty());
+		assert_eq!(LastRelayParent::<T>::get(), Some(relay_parent));
+		Ok(())
+	}
+
 	impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
 }
@@ -15,16 +15,43 @@ mod mock;
 #[cfg(test)]
 mod tests;
 
+pub mod migration;
+pub mod proof;
+pub mod reimbursement;
+pub mod types;
 pub mod weights;
 
+pub use proof::{RelayEvents, RelayParentStateRoot};
+pub use reimbursement::FeeShare;
+pub use types::*;
+
 #[cfg(feature = "runtime-benchmarks")]
 pub use benchmarking::BenchmarkHelper;
 
 #[frame::pallet]
+#[allow(clippy::useless_conversion)]
 pub mod pallet {
 	use super::*;
 	use crate::weights::WeightInfo;
-	use sp_runtime::traits::AtLeast32BitUnsigned;
+	use alloc::boxed::Box;
+	use cumulus_primitives_core::{relay_chain::BlockNumber as RelayBlockNumber, ParaId};
+	use frame_support::{
+		traits::{
+			fungible::{Balanced, Mutate},
+			tokens::Preservation,
+			Contains, UnixTime,
+		},
+		PalletId,
+	};
+	use on_demand_primitives::{MessageThreshold, ThresholdKind};
+	use sp_runtime::{
+		traits::{AccountIdConversion, AtLeast32BitUnsigned, Convert, MaybeConvert, One},
+		AccountId32, BoundedBTreeMap, FixedPointNumber, Perbill, RuntimeAppPublic,
+		SaturatedConversion,
+	};
+
+	/// The in-code storage version.
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
 
 	/// The module configuration trait.
 	#[pallet::config]
@@ -35,6 +62,14 @@ pub mod pallet {
 		/// The admin origin for managing the on-demand configuration.
 		type AdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
 
+		/// Relay chain balance type.
+		type Balance: Parameter
+			+ Member
+			+ Copy
+			+ MaybeSerializeDeserialize
+			+ MaxEncodedLen
+			+ AtLeast32BitUnsigned;
+
 		/// Block number type.
 		type BlockNumber: Parameter
 			+ Member
@@ -59,14 +94,92 @@ pub mod pallet {
 			+ MaybeSerializeDeserialize
 			+ MaxEncodedLen;
 
+		/// The kind of threshold set through `set_threshold_parameter`.
+		#[pallet::constant]
+		type ThresholdParameterKind: Get<ThresholdKind>;
+
+		/// The maximum number of thresholds which can be stored.
+		#[pallet::constant]
+		type MaxThresholds: Get<u32>;
+
+		/// The maximum number of changes which can be scheduled for a single block.
+		#[pallet::constant]
+		type MaxScheduledPerBlock: Get<u32>;
+
+		/// The maximum number of changes which can be scheduled for relay chain blocks.
+		#[pallet::constant]
+		type MaxRelayScheduled: Get<u32>;
+
+		/// The maximum number of entries of the change profile.
+		#[pallet::constant]
+		type MaxProfileEntries: Get<u32>;
+
+		/// Provides the current time, used for applying the change profile.
+		type UnixTime: UnixTime;
+
+		/// Converts a relay chain balance into the threshold parameter.
+		///
+		/// Used when the threshold parameter is adjusted to the on-demand spot price.
+		type PriceToThreshold: Convert<Self::Balance, Self::ThresholdParameter>;
+
+		/// The identifier of the collator authorities reporting the spot price.
+		type AuthorityId: Member + Parameter + RuntimeAppPublic + MaxEncodedLen;
+
+		/// The current collator authorities, e.g. the Aura authorities.
+		type Authorities: Contains<Self::AuthorityId>;
+
+		/// Returns the authority key of a collator account, e.g. from its session keys.
+		type AuthorityOf: MaybeConvert<Self::AccountId, Self::AuthorityId>;
+
+		/// The priority of unsigned spot price reports and order proofs.
+		#[pallet::constant]
+		type UnsignedPriority: Get<TransactionPriority>;
+
+		/// The id of this parachain.
+		type SelfParaId: Get<ParaId>;
+
+		/// The events of the relay chain runtime, used for finding orders.
+		type RelayEvents: RelayEvents;
+
+		/// Provides the relay parent state against which order proofs are verified.
+		type RelayState: RelayParentStateRoot;
+
+		/// The number of relay chain blocks for which recorded orders are kept.
+		///
+		/// Older orders get pruned and can no longer be recorded or reimbursed.
+		#[pallet::constant]
+		type OrderRetention: Get<RelayBlockNumber>;
+
+		/// The maximum number of relay chain blocks with recorded orders.
+		#[pallet::constant]
+		type MaxOrderBlocks: Get<u32>;
+
+		/// The currency in which collators get reimbursed for their orders.
+		type Currency: Mutate<Self::AccountId> + Balanced<Self::AccountId>;
+
+		/// The id of the pallet, used for deriving the account of the reimbursement pot.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+
+		/// Converts the price of an order in the relay chain currency into the reimbursed amount.
+		type OrderCostToBalance: Convert<Self::Balance, CurrencyBalanceOf<Self>>;
+
+		/// Provides the author of the current block.
+		type BlockAuthor: Get<Option<Self::AccountId>>;
+
 		/// Weight Info
 		type WeightInfo: WeightInfo;
 
 		#[cfg(feature = "runtime-benchmarks")]
-		type BenchmarkHelper: crate::BenchmarkHelper<Self::ThresholdParameter>;
+		type BenchmarkHelper: crate::BenchmarkHelper<
+			Self::ThresholdParameter,
+			Self::AccountId,
+			Self::AuthorityId,
+		>;
 	}
 
 	#[pallet::pallet]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(_);
 
 	/// Defines how often a new on-demand order is created, based on the number of slots.
@@ -78,12 +191,130 @@ pub mod pallet {
 	#[pallet::getter(fn slot_width)]
 	pub type SlotWidth<T: Config> = StorageValue<_, T::BlockNumber, ValueQuery>;
 
-	/// The threshold parameter stored in the runtime state.
+	/// The thresholds stored in the runtime state, by kind.
+	///
+	/// These will determine whether an on-demand order should be placed by a collator.
+	#[pallet::storage]
+	#[pallet::getter(fn thresholds)]
+	pub type Thresholds<T: Config> = StorageValue<
+		_,
+		BoundedBTreeMap<ThresholdKind, T::ThresholdParameter, T::MaxThresholds>,
+		ValueQuery,
+	>;
+
+	/// The maximum price collators are allowed to pay for a single on-demand order.
+	///
+	/// If the spot price on the relay chain exceeds this value, collators will skip placing an
+	/// order. `None` means there is no limit.
+	#[pallet::storage]
+	#[pallet::getter(fn max_order_price)]
+	pub type MaxOrderPrice<T: Config> = StorageValue<_, T::Balance, OptionQuery>;
+
+	/// The maximum number of relay chain blocks the parachain may go without a new block.
+	///
+	/// Once that many relay chain blocks passed since the last included parachain block, collators
+	/// will place an order regardless of the order placement criteria. `None` disables this.
+	#[pallet::storage]
+	#[pallet::getter(fn max_idle_relay_blocks)]
+	pub type MaxIdleRelayBlocks<T: Config> = StorageValue<_, T::BlockNumber, OptionQuery>;
+
+	/// The threshold of queued inbound messages after which collators place an order.
+	///
+	/// Downward and HRMP messages are only processed once the parachain produces a block. `None`
+	/// means queued messages don't affect order placement.
+	#[pallet::storage]
+	#[pallet::getter(fn inbound_message_threshold)]
+	pub type InboundMessageThreshold<T: Config> =
+		StorageValue<_, MessageThreshold<T::BlockNumber>, OptionQuery>;
+
+	/// Configuration changes scheduled for future parachain blocks.
+	///
+	/// The changes get applied in order at the start of the block they are scheduled for.
+	#[pallet::storage]
+	#[pallet::getter(fn scheduled_changes)]
+	pub type ScheduledChanges<T: Config> =
+		StorageMap<_, Twox64Concat, BlockNumberFor<T>, ScheduledChangesOf<T>, ValueQuery>;
+
+	/// Configuration changes scheduled for future relay chain blocks, ordered by relay chain
+	/// block.
 	///
-	/// This will determine whether an on-demand order should be placed by a collator.
+	/// The changes get applied at the end of the first parachain block built on top of the relay
+	/// chain block they are scheduled for, or a later one.
 	#[pallet::storage]
-	#[pallet::getter(fn threshold_parameter)]
-	pub type ThresholdParameter<T: Config> = StorageValue<_, T::ThresholdParameter, ValueQuery>;
+	#[pallet::getter(fn relay_scheduled_changes)]
+	pub type RelayScheduledChanges<T: Config> =
+		StorageValue<_, RelayScheduledChangesOf<T>, ValueQuery>;
+
+	/// Configuration changes recurring every period, e.g. every day.
+	#[pallet::storage]
+	#[pallet::getter(fn change_profile)]
+	pub type ChangeProfileConfig<T: Config> = StorageValue<_, ChangeProfileOf<T>, OptionQuery>;
+
+	/// The offset of the change profile entries currently in effect.
+	#[pallet::storage]
+	pub type ActiveProfileOffset<T: Config> = StorageValue<_, u32, OptionQuery>;
+
+	/// Adjusts the threshold parameter to the spot price reported by collators.
+	///
+	/// `None` means the threshold parameter is only set manually.
+	#[pallet::storage]
+	#[pallet::getter(fn adaptive_threshold)]
+	pub type AdaptiveThresholdConfig<T: Config> =
+		StorageValue<_, AdaptiveThreshold<T::Balance>, OptionQuery>;
+
+	/// The last spot price reported by a collator.
+	#[pallet::storage]
+	#[pallet::getter(fn last_spot_price)]
+	pub type LastSpotPrice<T: Config> =
+		StorageValue<_, SpotPriceReport<T::Balance, BlockNumberFor<T>>, OptionQuery>;
+
+	/// The on-demand orders placed for this parachain, by relay chain block and extrinsic index.
+	#[pallet::storage]
+	#[pallet::getter(fn orders)]
+	pub type Orders<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		RelayBlockNumber,
+		Twox64Concat,
+		u32,
+		OrderRecord<T::Balance>,
+		OptionQuery,
+	>;
+
+	/// The relay chain blocks with recorded orders, in ascending order.
+	///
+	/// Used for pruning the `Orders` older than `OrderRetention`.
+	#[pallet::storage]
+	#[pallet::getter(fn order_blocks)]
+	pub type OrderBlocks<T: Config> =
+		StorageValue<_, BoundedVec<RelayBlockNumber, T::MaxOrderBlocks>, ValueQuery>;
+
+	/// The share of transaction fees going to the reimbursement pot.
+	///
+	/// Only takes effect if the runtime handles transaction fees with `FeeShare`.
+	#[pallet::storage]
+	#[pallet::getter(fn reimbursement_share)]
+	pub type ReimbursementShare<T: Config> = StorageValue<_, Perbill, ValueQuery>;
+
+	/// The relay parent of the previous parachain block.
+	///
+	/// Orders placed after it produced the current block.
+	#[pallet::storage]
+	#[pallet::getter(fn last_relay_parent)]
+	pub type LastRelayParent<T: Config> = StorageValue<_, RelayBlockNumber, OptionQuery>;
+
+	/// The parachain block in which a collator got reimbursed last.
+	#[pallet::storage]
+	#[pallet::getter(fn last_reimbursement)]
+	pub type LastReimbursement<T: Config> = StorageValue<_, BlockNumberFor<T>, OptionQuery>;
+
+	/// The relay chain accounts paying for the orders of collator authorities.
+	///
+	/// Authorities without a payment account pay with the account of their own key.
+	#[pallet::storage]
+	#[pallet::getter(fn payment_accounts)]
+	pub type PaymentAccounts<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AuthorityId, AccountId32, OptionQuery>;
 
 	#[pallet::event]
 	#[pallet::generate_deposit(pub(super) fn deposit_event)]
@@ -92,23 +323,133 @@ pub mod pallet {
 		SlotWidthSet { width: T::BlockNumber },
 		/// Threshold parameter set.
 		ThresholdParameterSet { parameter: T::ThresholdParameter },
+		/// Maximum order price set.
+		MaxOrderPriceSet { price: Option<T::Balance> },
+		/// Maximum number of idle relay chain blocks set.
+		MaxIdleRelayBlocksSet { blocks: Option<T::BlockNumber> },
+		/// Inbound message threshold set.
+		InboundMessageThresholdSet { threshold: Option<MessageThreshold<T::BlockNumber>> },
+		/// Threshold of the given kind set.
+		ThresholdSet { kind: ThresholdKind, value: Option<T::ThresholdParameter> },
+		/// A configuration change got scheduled.
+		ChangeScheduled { when: BlockNumberFor<T>, change: ScheduledChangeOf<T> },
+		/// The configuration changes scheduled for a block got cancelled.
+		ScheduledChangesCancelled { when: BlockNumberFor<T> },
+		/// A scheduled configuration change couldn't be applied.
+		ScheduledChangeFailed { change: ScheduledChangeOf<T>, error: DispatchError },
+		/// A configuration change got scheduled for a relay chain block.
+		RelayChangeScheduled { when: RelayBlockNumber, change: ScheduledChangeOf<T> },
+		/// The configuration changes scheduled for a relay chain block got cancelled.
+		RelayScheduledChangesCancelled { when: RelayBlockNumber },
+		/// Change profile set.
+		ChangeProfileSet { profile: Option<ChangeProfileOf<T>> },
+		/// Adaptive threshold configuration set.
+		AdaptiveThresholdSet { config: Option<AdaptiveThreshold<T::Balance>> },
+		/// A collator reported the on-demand spot price.
+		SpotPriceReported { price: T::Balance },
+		/// An on-demand order placed on the relay chain got recorded.
+		OrderRecorded { payer: AccountId32, amount: T::Balance, relay_block: RelayBlockNumber },
+		/// Reimbursement share set.
+		ReimbursementShareSet { share: Perbill },
+		/// A collator got reimbursed for an on-demand order.
+		OrderReimbursed {
+			who: T::AccountId,
+			relay_block: RelayBlockNumber,
+			amount: CurrencyBalanceOf<T>,
+		},
+		/// The payment account of a collator authority set.
+		PaymentAccountSet { authority: T::AuthorityId, payer: Option<AccountId32> },
 	}
 
 	#[pallet::error]
 	#[derive(PartialEq)]
-	pub enum Error<T> {}
+	pub enum Error<T> {
+		/// The maximum number of thresholds is already stored.
+		TooManyThresholds,
+		/// Changes can only be scheduled for future blocks.
+		ScheduledInThePast,
+		/// The maximum number of changes is already scheduled for the block.
+		TooManyScheduledChanges,
+		/// The minimum of the adaptive threshold exceeds its maximum.
+		InvalidThresholdBounds,
+		/// The threshold parameter isn't adjusted to the spot price.
+		AdaptiveThresholdDisabled,
+		/// The spot price was already reported in this block.
+		SpotPriceAlreadyReported,
+		/// The relay chain block isn't proven by the relay parent state.
+		UnknownRelayBlock,
+		/// The order isn't proven by the events of the relay chain block.
+		OrderNotFound,
+		/// The order was already recorded.
+		OrderAlreadyRecorded,
+		/// The order is older than the retention period.
+		OrderExpired,
+		/// The maximum number of relay chain blocks with recorded orders is reached.
+		TooManyOrderBlocks,
+		/// The order wasn't recorded.
+		UnknownOrder,
+		/// Only the payer of the order can be reimbursed.
+		NotOrderPayer,
+		/// Only the author of the current block can be reimbursed.
+		NotBlockAuthor,
+		/// The payer of the order was already reimbursed.
+		AlreadyReimbursed,
+		/// A collator was already reimbursed in this block.
+		ReimbursementAlreadyClaimed,
+		/// The reimbursement pot can't cover the order.
+		InsufficientPot,
+		/// The account has no collator authority key.
+		NoAuthorityKey,
+		/// The order didn't produce the current block.
+		OrderNotForBlock,
+		/// The change profile has no period, or its entries aren't ordered within the period.
+		InvalidChangeProfile,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(now: BlockNumberFor<T>) -> Weight {
+			let changes = ScheduledChanges::<T>::take(now);
+			let weight = T::WeightInfo::on_initialize(changes.len() as u32)
+				.saturating_add(T::WeightInfo::prune_orders(Self::prune_orders()))
+				.saturating_add(T::WeightInfo::on_finalize(
+					T::MaxRelayScheduled::get(),
+					T::MaxProfileEntries::get(),
+				));
+
+			Self::apply_changes(changes);
+
+			weight
+		}
+
+		fn on_finalize(_now: BlockNumberFor<T>) {
+			// The relay parent is only known once the inherents got applied.
+			let relay_parent = T::RelayState::block_number();
+			Self::apply_relay_changes(relay_parent);
+			Self::apply_profile();
+
+			LastRelayParent::<T>::set(Some(relay_parent));
+		}
+	}
 
 	#[pallet::genesis_config]
 	#[derive(DefaultNoBound)]
 	pub struct GenesisConfig<T: Config> {
 		/// Initial threshold parameter.
 		pub threshold_parameter: T::ThresholdParameter,
+		/// Initial share of the transaction fees moved into the reimbursement pot.
+		pub reimbursement_share: Perbill,
 	}
 
 	#[pallet::genesis_build]
 	impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
 		fn build(&self) {
-			ThresholdParameter::<T>::set(self.threshold_parameter.clone());
+			Pallet::<T>::set_threshold_value(
+				T::ThresholdParameterKind::get(),
+				Some(self.threshold_parameter.clone()),
+			)
+			.expect("The threshold parameter fits into an empty map");
+			ReimbursementShare::<T>::set(self.reimbursement_share);
 		}
 	}
 
@@ -141,8 +482,679 @@ pub mod pallet {
 		) -> DispatchResult {
 			T::AdminOrigin::ensure_origin_or_root(origin)?;
 
-			ThresholdParameter::<T>::set(parameter.clone());
-			Self::deposit_event(Event::ThresholdParameterSet { parameter });
+			Self::set_threshold_value(T::ThresholdParameterKind::get(), Some(parameter))?;
+
+			Ok(())
+		}
+
+		/// Set the maximum price of a single on-demand order.
+		///
+		/// - `origin`: Must be Root or pass `AdminOrigin`.
+		/// - `price`: The maximum order price. `None` removes the limit.
+		#[pallet::call_index(2)]
+		#[pallet::weight(T::WeightInfo::set_max_order_price())]
+		pub fn set_max_order_price(
+			origin: OriginFor<T>,
+			price: Option<T::Balance>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin_or_root(origin)?;
+
+			MaxOrderPrice::<T>::set(price);
+			Self::deposit_event(Event::MaxOrderPriceSet { price });
+
+			Ok(())
+		}
+
+		/// Set the maximum number of relay chain blocks without a parachain block.
+		///
+		/// - `origin`: Must be Root or pass `AdminOrigin`.
+		/// - `blocks`: The number of relay chain blocks. `None` disables forced orders.
+		#[pallet::call_index(3)]
+		#[pallet::weight(T::WeightInfo::set_max_idle_relay_blocks())]
+		pub fn set_max_idle_relay_blocks(
+			origin: OriginFor<T>,
+			blocks: Option<T::BlockNumber>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin_or_root(origin)?;
+
+			MaxIdleRelayBlocks::<T>::set(blocks.clone());
+			Self::deposit_event(Event::MaxIdleRelayBlocksSet { blocks });
+
+			Ok(())
+		}
+
+		/// Set the threshold of queued inbound messages.
+		///
+		/// - `origin`: Must be Root or pass `AdminOrigin`.
+		/// - `threshold`: The inbound message threshold. `None` disables it.
+		#[pallet::call_index(4)]
+		#[pallet::weight(T::WeightInfo::set_inbound_message_threshold())]
+		pub fn set_inbound_message_threshold(
+			origin: OriginFor<T>,
+			threshold: Option<MessageThreshold<T::BlockNumber>>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin_or_root(origin)?;
+
+			InboundMessageThreshold::<T>::set(threshold.clone());
+			Self::deposit_event(Event::InboundMessageThresholdSet { threshold });
+
+			Ok(())
+		}
+
+		/// Set the threshold of the given kind.
+		///
+		/// - `origin`: Must be Root or pass `AdminOrigin`.
+		/// - `kind`: The kind of the threshold.
+		/// - `value`: The threshold. `None` removes it.
+		#[pallet::call_index(5)]
+		#[pallet::weight(T::WeightInfo::set_threshold())]
+		pub fn set_threshold(
+			origin: OriginFor<T>,
+			kind: ThresholdKind,
+			value: Option<T::ThresholdParameter>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin_or_root(origin)?;
+
+			Self::set_threshold_value(kind, value)?;
+
+			Ok(())
+		}
+
+		/// Schedule a configuration change for a future parachain block.
+		///
+		/// - `origin`: Must be Root or pass `AdminOrigin`.
+		/// - `when`: The parachain block at the start of which the change is applied.
+		/// - `change`: The configuration change.
+		#[pallet::call_index(6)]
+		#[pallet::weight(T::WeightInfo::schedule_change())]
+		pub fn schedule_change(
+			origin: OriginFor<T>,
+			when: BlockNumberFor<T>,
+			change: ScheduledChangeOf<T>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin_or_root(origin)?;
+
+			ensure!(
+				when > frame_system::Pallet::<T>::block_number(),
+				Error::<T>::ScheduledInThePast
+			);
+
+			ScheduledChanges::<T>::try_mutate(when, |changes| {
+				changes
+					.try_push(change.clone())
+					.map_err(|_| Error::<T>::TooManyScheduledChanges)
+			})?;
+			Self::deposit_event(Event::ChangeScheduled { when, change });
+
+			Ok(())
+		}
+
+		/// Cancel all the configuration changes scheduled for a parachain block.
+		///
+		/// - `origin`: Must be Root or pass `AdminOrigin`.
+		/// - `when`: The parachain block for which the changes were scheduled.
+		#[pallet::call_index(7)]
+		#[pallet::weight(T::WeightInfo::cancel_scheduled_changes())]
+		pub fn cancel_scheduled_changes(
+			origin: OriginFor<T>,
+			when: BlockNumberFor<T>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin_or_root(origin)?;
+
+			ScheduledChanges::<T>::remove(when);
+			Self::deposit_event(Event::ScheduledChangesCancelled { when });
+
+			Ok(())
+		}
+
+		/// Configure the adjustment of the threshold parameter to the on-demand spot price.
+		///
+		/// - `origin`: Must be Root or pass `AdminOrigin`.
+		/// - `config`: The adaptive threshold configuration. `None` disables the adjustment.
+		#[pallet::call_index(8)]
+		#[pallet::weight(T::WeightInfo::set_adaptive_threshold())]
+		pub fn set_adaptive_threshold(
+			origin: OriginFor<T>,
+			config: Option<AdaptiveThreshold<T::Balance>>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin_or_root(origin)?;
+
+			if let Some(config) = &config {
+				ensure!(config.min <= config.max, Error::<T>::InvalidThresholdBounds);
+			}
+
+			AdaptiveThresholdConfig::<T>::set(config.clone());
+			Self::deposit_event(Event::AdaptiveThresholdSet { config });
+
+			Ok(())
+		}
+
+		/// Report the on-demand spot price of the relay chain.
+		///
+		/// The threshold parameter is set to the price times the configured margin, within the
+		/// configured bounds. Only one report per block is accepted, which must be signed by one
+		/// of the current collator authorities.
+		///
+		/// - `origin`: Must be unsigned.
+		/// - `payload`: The spot price report.
+		/// - `signature`: Signature of the payload by the reporting authority.
+		#[pallet::call_index(9)]
+		#[pallet::weight(T::WeightInfo::report_spot_price())]
+		pub fn report_spot_price(
+			origin: OriginFor<T>,
+			payload: SpotPricePayloadOf<T>,
+			// The signature is verified in `validate_unsigned`.
+			_signature: <T::AuthorityId as RuntimeAppPublic>::Signature,
+		) -> DispatchResult {
+			ensure_none(origin)?;
+			let price = payload.price;
+
+			let config = Self::ensure_can_report()?;
+			LastSpotPrice::<T>::set(Some(SpotPriceReport {
+				price,
+				reported_at: frame_system::Pallet::<T>::block_number(),
+			}));
+			Self::deposit_event(Event::SpotPriceReported { price });
+
+			let margin_price: u128 =
+				config.margin.saturating_mul_int(price.saturated_into::<u128>());
+			let target = T::Balance::saturated_from(margin_price).clamp(config.min, config.max);
+			let parameter = T::PriceToThreshold::convert(target);
+
+			Self::set_threshold_value(T::ThresholdParameterKind::get(), Some(parameter))?;
+
+			Ok(())
+		}
+
+		/// Record an on-demand order placed for this parachain on the relay chain.
+		///
+		/// The order is verified against the state of the relay parent, so no signature is
+		/// required.
+		///
+		/// - `origin`: Must be unsigned.
+		/// - `proof`: Proof of the `OnDemandOrderPlaced` event.
+		#[pallet::call_index(10)]
+		#[pallet::weight(T::WeightInfo::record_order(proof.encoded_size() as u32))]
+		pub fn record_order(
+			origin: OriginFor<T>,
+			proof: Box<OrderProof<T::Balance>>,
+		) -> DispatchResult {
+			ensure_none(origin)?;
+
+			let (relay_block, extrinsic_index) = Self::verify_order(&proof)?;
+			let OrderProof { payer, amount, .. } = *proof;
+
+			OrderBlocks::<T>::try_mutate(|blocks| match blocks.binary_search(&relay_block) {
+				Ok(_) => Ok(()),
+				Err(index) => blocks
+					.try_insert(index, relay_block)
+					.map_err(|_| Error::<T>::TooManyOrderBlocks),
+			})?;
+			Orders::<T>::insert(
+				relay_block,
+				extrinsic_index,
+				OrderRecord { payer: payer.clone(), amount, reimbursed: false },
+			);
+			Self::deposit_event(Event::OrderRecorded { payer, amount, relay_block });
+
+			Ok(())
+		}
+
+		/// Set the share of transaction fees going to the reimbursement pot.
+		///
+		/// - `origin`: Must be Root or pass `AdminOrigin`.
+		/// - `share`: The share of transaction fees.
+		#[pallet::call_index(11)]
+		#[pallet::weight(T::WeightInfo::set_reimbursement_share())]
+		pub fn set_reimbursement_share(origin: OriginFor<T>, share: Perbill) -> DispatchResult {
+			T::AdminOrigin::ensure_origin_or_root(origin)?;
+
+			ReimbursementShare::<T>::set(share);
+			Self::deposit_event(Event::ReimbursementShareSet { share });
+
+			Ok(())
+		}
+
+		/// Reimburse the author of the current block for the on-demand order producing the block.
+		///
+		/// The order must have been proven with `record_order` and paid for by the payment account
+		/// of the caller's collator authority. It must have been placed after the relay parent of
+		/// the previous parachain block, and up to the current relay parent. Only one order is
+		/// reimbursed per block. The transaction is free if the reimbursement succeeds.
+		///
+		/// - `origin`: Must be signed by the author of the current block.
+		/// - `relay_block`: The relay chain block containing the order.
+		/// - `extrinsic_index`: The index of the order extrinsic within the relay chain block.
+		#[pallet::call_index(12)]
+		#[pallet::weight(T::WeightInfo::claim_reimbursement())]
+		pub fn claim_reimbursement(
+			origin: OriginFor<T>,
+			relay_block: RelayBlockNumber,
+			extrinsic_index: u32,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			ensure!(T::BlockAuthor::get().as_ref() == Some(&who), Error::<T>::NotBlockAuthor);
+			let now = frame_system::Pallet::<T>::block_number();
+			ensure!(
+				LastReimbursement::<T>::get() != Some(now),
+				Error::<T>::ReimbursementAlreadyClaimed
+			);
+
+			let amount = Orders::<T>::try_mutate(relay_block, extrinsic_index, |record| {
+				let record = record.as_mut().ok_or(Error::<T>::UnknownOrder)?;
+				let payer = T::AuthorityOf::maybe_convert(who.clone())
+					.and_then(|authority| Self::payer_of(&authority));
+				ensure!(payer.as_ref() == Some(&record.payer), Error::<T>::NotOrderPayer);
+				ensure!(!record.reimbursed, Error::<T>::AlreadyReimbursed);
+				ensure!(Self::produced_current_block(relay_block), Error::<T>::OrderNotForBlock);
+
+				let amount = T::OrderCostToBalance::convert(record.amount);
+				T::Currency::transfer(&Self::pot(), &who, amount, Preservation::Expendable)
+					.map_err(|_| Error::<T>::InsufficientPot)?;
+				record.reimbursed = true;
+
+				Ok::<_, DispatchError>(amount)
+			})?;
+			LastReimbursement::<T>::set(Some(now));
+			Self::deposit_event(Event::OrderReimbursed { who, relay_block, amount });
+
+			Ok(Pays::No.into())
+		}
+
+		/// Set the relay chain account paying for the orders of the caller's collator authority.
+		///
+		/// Collators place orders with the payment account of the elected authority, so the
+		/// mapping has to be known to all of them.
+		///
+		/// - `origin`: Must be signed by an account with a collator authority key.
+		/// - `payer`: The payment account, or `None` to pay with the account of the authority key.
+		#[pallet::call_index(13)]
+		#[pallet::weight(T::WeightInfo::set_payment_account())]
+		pub fn set_payment_account(
+			origin: OriginFor<T>,
+			payer: Option<AccountId32>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let authority = T::AuthorityOf::maybe_convert(who).ok_or(Error::<T>::NoAuthorityKey)?;
+
+			PaymentAccounts::<T>::set(&authority, payer.clone());
+			Self::deposit_event(Event::PaymentAccountSet { authority, payer });
+
+			Ok(())
+		}
+
+		/// Schedule a configuration change for a future relay chain block.
+		///
+		/// Unlike parachain blocks, relay chain blocks are produced at a steady rate, even while
+		/// the parachain doesn't produce any blocks.
+		///
+		/// - `origin`: Must be Root or pass `AdminOrigin`.
+		/// - `when`: The relay chain block from which on the change is applied.
+		/// - `change`: The configuration change.
+		#[pallet::call_index(14)]
+		#[pallet::weight(T::WeightInfo::schedule_relay_change())]
+		pub fn schedule_relay_change(
+			origin: OriginFor<T>,
+			when: RelayBlockNumber,
+			change: ScheduledChangeOf<T>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin_or_root(origin)?;
+
+			ensure!(when > T::RelayState::block_number(), Error::<T>::ScheduledInThePast);
+
+			RelayScheduledChanges::<T>::try_mutate(|changes| {
+				// Changes scheduled for the same relay chain block are applied in order.
+				let index = changes.partition_point(|(scheduled, _)| *scheduled <= when);
+				changes
+					.try_insert(index, (when, change.clone()))
+					.map_err(|_| Error::<T>::TooManyScheduledChanges)
+			})?;
+			Self::deposit_event(Event::RelayChangeScheduled { when, change });
+
+			Ok(())
+		}
+
+		/// Cancel all the configuration changes scheduled for a relay chain block.
+		///
+		/// - `origin`: Must be Root or pass `AdminOrigin`.
+		/// - `when`: The relay chain block for which the changes were scheduled.
+		#[pallet::call_index(15)]
+		#[pallet::weight(T::WeightInfo::cancel_relay_scheduled_changes())]
+		pub fn cancel_relay_scheduled_changes(
+			origin: OriginFor<T>,
+			when: RelayBlockNumber,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin_or_root(origin)?;
+
+			RelayScheduledChanges::<T>::mutate(|changes| {
+				changes.retain(|(scheduled, _)| *scheduled != when)
+			});
+			Self::deposit_event(Event::RelayScheduledChangesCancelled { when });
+
+			Ok(())
+		}
+
+		/// Set the configuration changes recurring every period, e.g. every day.
+		///
+		/// The entries of the current time of the period get applied at the end of the block.
+		///
+		/// - `origin`: Must be Root or pass `AdminOrigin`.
+		/// - `profile`: The change profile. `None` stops applying it.
+		#[pallet::call_index(16)]
+		#[pallet::weight(T::WeightInfo::set_change_profile())]
+		pub fn set_change_profile(
+			origin: OriginFor<T>,
+			profile: Option<ChangeProfileOf<T>>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin_or_root(origin)?;
+
+			if let Some(profile) = &profile {
+				let offsets = profile.entries.iter().map(|(offset, _)| *offset);
+				ensure!(
+					profile.period > 0 &&
+						offsets.clone().all(|offset| offset < profile.period) &&
+						offsets.clone().zip(offsets.skip(1)).all(|(a, b)| a <= b),
+					Error::<T>::InvalidChangeProfile
+				);
+			}
+
+			ChangeProfileConfig::<T>::set(profile.clone());
+			ActiveProfileOffset::<T>::kill();
+			Self::deposit_event(Event::ChangeProfileSet { profile });
+
+			Ok(())
+		}
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			match call {
+				Call::report_spot_price { payload, signature } => {
+					if Self::ensure_can_report().is_err() {
+						return InvalidTransaction::Stale.into()
+					}
+
+					let now = frame_system::Pallet::<T>::block_number();
+					if payload.block_number < now {
+						return InvalidTransaction::Stale.into()
+					}
+					if payload.block_number > now {
+						return InvalidTransaction::Future.into()
+					}
+					if !T::Authorities::contains(&payload.authority) {
+						return InvalidTransaction::BadSigner.into()
+					}
+					let valid = payload
+						.using_encoded(|encoded| payload.authority.verify(&encoded, signature));
+					if !valid {
+						return InvalidTransaction::BadProof.into()
+					}
+
+					ValidTransaction::with_tag_prefix("OnDemandSpotPrice")
+						.priority(T::UnsignedPriority::get())
+						.and_provides(frame_system::Pallet::<T>::block_number())
+						.longevity(1)
+						.propagate(true)
+						.build()
+				},
+				Call::record_order { proof } => {
+					let order = match Self::verify_order(proof) {
+						Ok(order) => order,
+						Err(e)
+							if e == Error::<T>::OrderAlreadyRecorded.into() ||
+								e == Error::<T>::OrderExpired.into() =>
+							return InvalidTransaction::Stale.into(),
+						Err(_) => return InvalidTransaction::BadProof.into(),
+					};
+
+					ValidTransaction::with_tag_prefix("OnDemandOrder")
+						.priority(T::UnsignedPriority::get())
+						.and_provides(order)
+						.longevity(5)
+						.propagate(true)
+						.build()
+				},
+				_ => InvalidTransaction::Call.into(),
+			}
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The account of the reimbursement pot.
+		pub fn pot() -> T::AccountId {
+			T::PalletId::get().into_account_truncating()
+		}
+
+		/// The threshold of the kind set through `set_threshold_parameter`.
+		pub fn threshold_parameter() -> T::ThresholdParameter {
+			Thresholds::<T>::get()
+				.get(&T::ThresholdParameterKind::get())
+				.cloned()
+				.unwrap_or_default()
+		}
+
+		/// Returns the threshold of the given kind, if set.
+		pub fn threshold(kind: ThresholdKind) -> Option<T::ThresholdParameter> {
+			Thresholds::<T>::get().get(&kind).cloned()
+		}
+
+		/// Returns the relay chain account paying for the orders of `authority`.
+		///
+		/// Falls back to the account of the authority key if no payment account is set.
+		pub fn payer_of(authority: &T::AuthorityId) -> Option<AccountId32> {
+			PaymentAccounts::<T>::get(authority)
+				.or_else(|| <[u8; 32]>::try_from(authority.to_raw_vec()).ok().map(AccountId32::new))
+		}
+
+		/// Returns a spot price report signed by a collator authority with a key in the keystore.
+		///
+		/// The report is meant for the block built on top of the current one. Returns `None` if
+		/// the threshold isn't adjusted to the spot price, or if the keystore has no authority key.
+		pub fn signed_spot_price_report(price: T::Balance) -> Option<Call<T>> {
+			AdaptiveThresholdConfig::<T>::get()?;
+
+			let authority = T::AuthorityId::all().into_iter().find(T::Authorities::contains)?;
+			let payload = SpotPricePayload {
+				price,
+				block_number: frame_system::Pallet::<T>::block_number() + One::one(),
+				authority,
+			};
+			let signature = payload.using_encoded(|encoded| payload.authority.sign(&encoded))?;
+
+			Some(Call::report_spot_price { payload, signature })
+		}
+
+		/// Ensures a spot price report is accepted, returning the adaptive threshold
+		/// configuration.
+		fn ensure_can_report() -> Result<AdaptiveThreshold<T::Balance>, DispatchError> {
+			let config =
+				AdaptiveThresholdConfig::<T>::get().ok_or(Error::<T>::AdaptiveThresholdDisabled)?;
+
+			let now = frame_system::Pallet::<T>::block_number();
+			if let Some(last) = LastSpotPrice::<T>::get() {
+				ensure!(last.reported_at < now, Error::<T>::SpotPriceAlreadyReported);
+			}
+
+			Ok(config)
+		}
+
+		/// Verifies the order proof against the state of the relay parent.
+		///
+		/// Returns the relay chain block number and the index of the extrinsic placing the order.
+		pub(crate) fn verify_order(
+			proof: &OrderProof<T::Balance>,
+		) -> Result<(RelayBlockNumber, u32), DispatchError> {
+			let relay_block = proof.header.number;
+			// Expired orders are pruned, so they must not be recorded again.
+			ensure!(
+				!Self::is_expired(relay_block, T::RelayState::block_number()),
+				Error::<T>::OrderExpired
+			);
+			ensure!(
+				!Orders::<T>::contains_key(relay_block, proof.extrinsic_index),
+				Error::<T>::OrderAlreadyRecorded
+			);
+
+			let block_hash = crate::proof::read_value(
+				&T::RelayState::state_root(),
+				proof.block_hash_proof.clone(),
+				&crate::proof::block_hash_key(relay_block),
+			)
+			.ok_or(Error::<T>::UnknownRelayBlock)?;
+			ensure!(block_hash == proof.header.hash().encode(), Error::<T>::UnknownRelayBlock);
+
+			let events = crate::proof::read_value(
+				&proof.header.state_root,
+				proof.events_proof.clone(),
+				&crate::proof::events_key(),
+			)
+			.ok_or(Error::<T>::OrderNotFound)?;
+			ensure!(
+				crate::proof::contains_order::<T::RelayEvents>(
+					&events,
+					proof.extrinsic_index,
+					T::SelfParaId::get(),
+					proof.amount.saturated_into(),
+					&proof.payer,
+				),
+				Error::<T>::OrderNotFound
+			);
+
+			Ok((relay_block, proof.extrinsic_index))
+		}
+
+		/// Returns whether an order placed in `relay_block` produced the current block.
+		fn produced_current_block(relay_block: RelayBlockNumber) -> bool {
+			LastRelayParent::<T>::get().is_none_or(|last| relay_block > last) &&
+				relay_block <= T::RelayState::block_number()
+		}
+
+		/// Removes the orders older than `OrderRetention`.
+		///
+		/// Returns the number of relay chain blocks whose orders got pruned.
+		pub(crate) fn prune_orders() -> u32 {
+			let now = T::RelayState::block_number();
+			let mut blocks = OrderBlocks::<T>::get();
+			let expired = blocks.iter().take_while(|block| Self::is_expired(**block, now)).count();
+			if expired == 0 {
+				return 0
+			}
+
+			for block in &blocks[..expired] {
+				let _ = Orders::<T>::clear_prefix(block, u32::MAX, None);
+			}
+			blocks.retain(|block| !Self::is_expired(*block, now));
+			OrderBlocks::<T>::set(blocks);
+
+			expired as u32
+		}
+
+		/// Returns whether the orders of `relay_block` are older than `OrderRetention`.
+		fn is_expired(relay_block: RelayBlockNumber, now: RelayBlockNumber) -> bool {
+			relay_block.saturating_add(T::OrderRetention::get()) < now
+		}
+
+		/// Applies the scheduled configuration changes in order.
+		fn apply_changes(changes: impl IntoIterator<Item = ScheduledChangeOf<T>>) {
+			for change in changes {
+				if let Err(error) = Self::apply_change(change.clone()) {
+					Self::deposit_event(Event::ScheduledChangeFailed { change, error });
+				}
+			}
+		}
+
+		/// Applies the changes scheduled for relay chain blocks up to `relay_parent`.
+		fn apply_relay_changes(relay_parent: RelayBlockNumber) {
+			let mut changes = RelayScheduledChanges::<T>::get().into_inner();
+			let due = changes.partition_point(|(when, _)| *when <= relay_parent);
+			if due == 0 {
+				return
+			}
+
+			let pending = changes.split_off(due);
+			RelayScheduledChanges::<T>::set(BoundedVec::truncate_from(pending));
+			Self::apply_changes(changes.into_iter().map(|(_, change)| change));
+		}
+
+		/// Applies the entries of the change profile for the current time, unless they are
+		/// already in effect.
+		fn apply_profile() {
+			let Some(profile) = ChangeProfileConfig::<T>::get() else { return };
+			let now = (T::UnixTime::now().as_secs() % profile.period as u64) as u32;
+
+			// Before the first offset, the entries of the last offset of the previous period are
+			// still in effect.
+			let offsets = profile.entries.iter().map(|(offset, _)| *offset);
+			let Some(active) =
+				offsets.clone().filter(|offset| *offset <= now).last().or(offsets.last())
+			else {
+				return
+			};
+			if ActiveProfileOffset::<T>::get() == Some(active) {
+				return
+			}
+
+			ActiveProfileOffset::<T>::set(Some(active));
+			Self::apply_changes(
+				profile
+					.entries
+					.into_iter()
+					.filter(|(offset, _)| *offset == active)
+					.map(|(_, change)| change),
+			);
+		}
+
+		/// Applies a scheduled configuration change, emitting the same event as the corresponding
+		/// call.
+		pub(crate) fn apply_change(change: ScheduledChangeOf<T>) -> DispatchResult {
+			match change {
+				ScheduledChange::SlotWidth(width) => {
+					SlotWidth::<T>::set(width.clone());
+					Self::deposit_event(Event::SlotWidthSet { width });
+				},
+				ScheduledChange::ThresholdParameter(parameter) => {
+					Self::set_threshold_value(T::ThresholdParameterKind::get(), Some(parameter))?;
+				},
+				ScheduledChange::Threshold { kind, value } => {
+					Self::set_threshold_value(kind, value)?;
+				},
+			}
+
+			Ok(())
+		}
+
+		/// Sets or removes the threshold of the given kind.
+		///
+		/// Emits `ThresholdSet`, followed by `ThresholdParameterSet` when the threshold parameter
+		/// changed, so that nodes can keep their cached settings up to date from the events.
+		pub(crate) fn set_threshold_value(
+			kind: ThresholdKind,
+			value: Option<T::ThresholdParameter>,
+		) -> DispatchResult {
+			Thresholds::<T>::try_mutate(|thresholds| -> DispatchResult {
+				match value.clone() {
+					Some(value) => {
+						thresholds
+							.try_insert(kind, value)
+							.map_err(|_| Error::<T>::TooManyThresholds)?;
+					},
+					None => {
+						thresholds.remove(&kind);
+					},
+				}
+				Ok(())
+			})?;
+
+			Self::deposit_event(Event::ThresholdSet { kind, value: value.clone() });
+			if kind == T::ThresholdParameterKind::get() {
+				Self::deposit_event(Event::ThresholdParameterSet {
+					parameter: value.unwrap_or_default(),
+				});
+			}
 
 			Ok(())
 		}
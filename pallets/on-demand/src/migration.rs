@@ -0,0 +1,64 @@
+//! Storage migrations for pallet-on-demand.
+
+use super::*;
+
+pub mod v1 {
+	use super::*;
+	use frame_support::{migrations::VersionedMigration, traits::UncheckedOnRuntimeUpgrade};
+
+	mod v0 {
+		use super::*;
+
+		/// The single threshold parameter, replaced by `Thresholds`.
+		#[frame_support::storage_alias]
+		pub type ThresholdParameter<T: Config> =
+			StorageValue<Pallet<T>, <T as Config>::ThresholdParameter, OptionQuery>;
+	}
+
+	/// Moves the single threshold parameter into the thresholds, under
+	/// `Config::ThresholdParameterKind`.
+	pub struct UncheckedMigrateToV1<T>(PhantomData<T>);
+
+	impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateToV1<T> {
+		fn on_runtime_upgrade() -> Weight {
+			let Some(parameter) = v0::ThresholdParameter::<T>::take() else {
+				return T::DbWeight::get().reads(1)
+			};
+
+			if Pallet::<T>::set_threshold_value(T::ThresholdParameterKind::get(), Some(parameter))
+				.is_err()
+			{
+				frame_support::defensive!("Failed to migrate the threshold parameter");
+			}
+
+			T::DbWeight::get().reads_writes(2, 2)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<alloc::vec::Vec<u8>, sp_runtime::TryRuntimeError> {
+			Ok(v0::ThresholdParameter::<T>::get().encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: alloc::vec::Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+			let parameter = Option::<T::ThresholdParameter>::decode(&mut &state[..])
+				.map_err(|_| "Failed to decode the threshold parameter")?;
+
+			ensure!(!v0::ThresholdParameter::<T>::exists(), "The old value must be removed");
+			ensure!(
+				Pallet::<T>::threshold(T::ThresholdParameterKind::get()) == parameter,
+				"The threshold parameter must be migrated"
+			);
+			Ok(())
+		}
+	}
+
+	/// Migrates the storage from version 0 to version 1.
+	pub type MigrateToV1<T> = VersionedMigration<
+		0,
+		1,
+		UncheckedMigrateToV1<T>,
+		Pallet<T>,
+		<T as frame_system::Config>::DbWeight,
+	>;
+}
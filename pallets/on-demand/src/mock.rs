@@ -1,10 +1,18 @@
+use cumulus_primitives_core::ParaId;
 use frame::{deps::sp_io, prelude::*, runtime::types_common::BlockNumber};
-use frame_support::{parameter_types, traits::Everything};
+use frame_support::{
+	parameter_types,
+	traits::{Contains, Everything, UnixTime},
+	PalletId,
+};
 use frame_system::EnsureRoot;
+use on_demand_primitives::ThresholdKind;
 use sp_core::{ConstU64, H256};
 use sp_runtime::{
-	traits::{BlakeTwo256, IdentityLookup},
-	BuildStorage,
+	testing::UintAuthorityId,
+	traits::{BlakeTwo256, Identity, IdentityLookup, MaybeConvert},
+	transaction_validity::TransactionPriority,
+	AccountId32, BuildStorage,
 };
 
 type Block = frame_system::mocking::MockBlock<Test>;
@@ -23,6 +31,84 @@ frame_support::construct_runtime!(
 parameter_types! {
 	pub const BlockHashCount: u64 = 250;
 	pub const SS58Prefix: u8 = 42;
+	pub const FeeThresholdKind: ThresholdKind = ThresholdKind::Fees;
+	pub const SelfParaId: ParaId = ParaId::new(2000);
+	pub static RelayStateRoot: H256 = H256::zero();
+	pub static RelayBlockNumber: u32 = 1;
+	pub const OnDemandPalletId: PalletId = PalletId(*b"py/ondmd");
+	pub static Author: Option<AccountId> = None;
+	pub static Authorities: Vec<u64> = vec![];
+	pub static AuthorityKeys: Vec<(AccountId, u64)> = vec![];
+	pub static Now: u64 = 0;
+}
+
+pub struct Time;
+impl UnixTime for Time {
+	fn now() -> core::time::Duration {
+		core::time::Duration::from_secs(Now::get())
+	}
+}
+
+pub struct RelayState;
+impl crate::RelayParentStateRoot for RelayState {
+	fn state_root() -> H256 {
+		RelayStateRoot::get()
+	}
+
+	fn block_number() -> u32 {
+		RelayBlockNumber::get()
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn set_state_root(root: H256) {
+		RelayStateRoot::set(root);
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn set_block_number(number: u32) {
+		RelayBlockNumber::set(number);
+	}
+}
+
+/// A subset of the relay chain events.
+#[derive(Encode, Decode, Debug, PartialEq)]
+pub enum RelayEvent {
+	/// An event carrying arbitrary bytes, e.g. an XCM message.
+	#[codec(index = 10)]
+	Payload(Vec<u8>),
+	#[codec(index = 66)]
+	OnDemand(RelayOnDemandEvent),
+}
+
+/// The events of the relay chain on-demand pallet.
+#[derive(Encode, Decode, Debug, PartialEq)]
+pub enum RelayOnDemandEvent {
+	OnDemandOrderPlaced { para_id: ParaId, spot_price: u128, ordered_by: AccountId32 },
+	SpotPriceSet { spot_price: u128 },
+}
+
+pub struct RelayChainEvents;
+impl crate::RelayEvents for RelayChainEvents {
+	type Event = RelayEvent;
+
+	fn order_placed(event: &RelayEvent) -> Option<(ParaId, u128, AccountId32)> {
+		match event {
+			RelayEvent::OnDemand(RelayOnDemandEvent::OnDemandOrderPlaced {
+				para_id,
+				spot_price,
+				ordered_by,
+			}) => Some((*para_id, *spot_price, ordered_by.clone())),
+			_ => None,
+		}
+	}
+
+	fn order_placed_event(para_id: ParaId, spot_price: u128, payer: AccountId32) -> RelayEvent {
+		RelayEvent::OnDemand(RelayOnDemandEvent::OnDemandOrderPlaced {
+			para_id,
+			spot_price,
+			ordered_by: payer,
+		})
+	}
 }
 
 impl frame_system::Config for Test {
@@ -73,20 +159,71 @@ impl pallet_balances::Config for Test {
 	type MaxFreezes = ();
 }
 
+/// The authorities set in `Authorities`.
+pub struct CollatorAuthorities;
+impl Contains<UintAuthorityId> for CollatorAuthorities {
+	fn contains(authority: &UintAuthorityId) -> bool {
+		Authorities::get().contains(&authority.0)
+	}
+}
+
+/// Returns the authority key set in `AuthorityKeys`.
+pub struct AuthorityOf;
+impl MaybeConvert<AccountId, UintAuthorityId> for AuthorityOf {
+	fn maybe_convert(who: AccountId) -> Option<UintAuthorityId> {
+		AuthorityKeys::get()
+			.into_iter()
+			.find_map(|(account, key)| (account == who).then_some(UintAuthorityId(key)))
+	}
+}
+
 #[cfg(feature = "runtime-benchmarks")]
 pub struct BenchHelper;
 #[cfg(feature = "runtime-benchmarks")]
-impl crate::BenchmarkHelper<Balance> for BenchHelper {
-	fn mock_threshold_parameter() -> Balance {
-		1_000u32.into()
+impl crate::BenchmarkHelper<Balance, AccountId, UintAuthorityId> for BenchHelper {
+	fn max_threshold_parameter() -> Balance {
+		Balance::MAX
+	}
+
+	fn set_block_author(author: AccountId) {
+		Author::set(Some(author));
+	}
+
+	fn add_authority(authority: UintAuthorityId) {
+		Authorities::mutate(|authorities| authorities.push(authority.0));
+	}
+
+	fn set_authority_key(who: AccountId, authority: UintAuthorityId) {
+		AuthorityKeys::mutate(|keys| keys.push((who, authority.0)));
 	}
 }
 
 impl crate::Config for Test {
 	type RuntimeEvent = RuntimeEvent;
 	type AdminOrigin = EnsureRoot<AccountId>;
+	type Balance = Balance;
 	type BlockNumber = BlockNumber;
 	type ThresholdParameter = Balance; // Represents fee threshold.
+	type ThresholdParameterKind = FeeThresholdKind;
+	type MaxThresholds = ConstU32<4>;
+	type MaxScheduledPerBlock = ConstU32<4>;
+	type MaxRelayScheduled = ConstU32<4>;
+	type MaxProfileEntries = ConstU32<4>;
+	type UnixTime = Time;
+	type PriceToThreshold = Identity;
+	type AuthorityId = UintAuthorityId;
+	type Authorities = CollatorAuthorities;
+	type AuthorityOf = AuthorityOf;
+	type UnsignedPriority = ConstU64<{ TransactionPriority::MAX }>;
+	type SelfParaId = SelfParaId;
+	type RelayEvents = RelayChainEvents;
+	type RelayState = RelayState;
+	type OrderRetention = ConstU32<10>;
+	type MaxOrderBlocks = ConstU32<4>;
+	type Currency = Balances;
+	type PalletId = OnDemandPalletId;
+	type OrderCostToBalance = Identity;
+	type BlockAuthor = Author;
 	#[cfg(feature = "runtime-benchmarks")]
 	type BenchmarkHelper = BenchHelper;
 	type WeightInfo = ();
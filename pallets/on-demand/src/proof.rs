@@ -0,0 +1,208 @@
+//! Verification of relay chain storage proofs for on-demand orders.
+//!
+//! An order is proven in two steps: the hash of the relay chain block containing the order is
+//! read from `System::BlockHash` in the state of the relay parent, and the order event is then
+//! looked up in `System::Events` in the state of that block.
+//!
+//! The events are decoded record by record using the relay chain event type, so that an order can
+//! only be matched against an actual `OnDemandOrderPlaced` event.
+
+use alloc::vec::Vec;
+use codec::{Codec, Compact, Decode, Encode};
+use cumulus_primitives_core::{
+	relay_chain::{BlockNumber as RelayBlockNumber, Hash as RelayHash},
+	ParaId,
+};
+use frame::deps::frame_system::Phase;
+use frame_support::{storage::storage_prefix, StorageHasher, Twox64Concat};
+use sp_runtime::{traits::BlakeTwo256, AccountId32};
+use sp_trie::{read_trie_value, LayoutV1, StorageProof};
+
+/// Provides the state root and the block number of the relay parent of the current block.
+pub trait RelayParentStateRoot {
+	/// Returns the state root of the relay parent.
+	fn state_root() -> RelayHash;
+
+	/// Returns the block number of the relay parent.
+	fn block_number() -> RelayBlockNumber;
+
+	/// Sets the state root of the relay parent.
+	#[cfg(feature = "runtime-benchmarks")]
+	fn set_state_root(_root: RelayHash) {}
+
+	/// Sets the block number of the relay parent.
+	#[cfg(feature = "runtime-benchmarks")]
+	fn set_block_number(_number: RelayBlockNumber) {}
+}
+
+/// The events of the relay chain runtime.
+pub trait RelayEvents {
+	/// The relay chain runtime event.
+	type Event: Codec;
+
+	/// Returns the para id, the spot price and the payer if the event is an
+	/// `OnDemandOrderPlaced` event.
+	fn order_placed(event: &Self::Event) -> Option<(ParaId, u128, AccountId32)>;
+
+	/// Creates an `OnDemandOrderPlaced` event.
+	#[cfg(any(test, feature = "runtime-benchmarks"))]
+	fn order_placed_event(para_id: ParaId, spot_price: u128, payer: AccountId32) -> Self::Event;
+}
+
+/// An event record of the relay chain, as stored in `System::Events`.
+#[derive(Encode, Decode)]
+pub struct RelayEventRecord<Event> {
+	/// The phase of the block the event was emitted in.
+	pub phase: Phase,
+	/// The event itself.
+	pub event: Event,
+	/// The topics of the event.
+	pub topics: Vec<RelayHash>,
+}
+
+/// The storage key of `System::BlockHash` for the given relay chain block.
+pub fn block_hash_key(number: RelayBlockNumber) -> Vec<u8> {
+	let mut key = storage_prefix(b"System", b"BlockHash").to_vec();
+	key.extend(Twox64Concat::hash(&number.encode()));
+	key
+}
+
+/// The storage key of `System::Events`.
+pub fn events_key() -> Vec<u8> {
+	storage_prefix(b"System", b"Events").to_vec()
+}
+
+/// Reads the value stored under `key` from the proof, checking it against `root`.
+///
+/// Returns `None` if the proof is invalid or doesn't contain the value.
+pub fn read_value(root: &RelayHash, proof: StorageProof, key: &[u8]) -> Option<Vec<u8>> {
+	let db = proof.into_memory_db::<BlakeTwo256>();
+	read_trie_value::<LayoutV1<BlakeTwo256>, _>(&db, root, key, None, None).ok()?
+}
+
+/// Returns whether the encoded `System::Events` contain the `OnDemandOrderPlaced` event emitted
+/// by the extrinsic at `extrinsic_index`.
+///
+/// Events which can't be decoded fail the lookup, as record boundaries can't be found without
+/// decoding them.
+pub fn contains_order<R: RelayEvents>(
+	events: &[u8],
+	extrinsic_index: u32,
+	para_id: ParaId,
+	amount: u128,
+	payer: &AccountId32,
+) -> bool {
+	let input = &mut &events[..];
+	let Ok(Compact(count)) = Compact::<u32>::decode(input) else { return false };
+
+	for _ in 0..count {
+		let Ok(record) = RelayEventRecord::<R::Event>::decode(input) else { return false };
+		if record.phase != Phase::ApplyExtrinsic(extrinsic_index) {
+			continue
+		}
+		if let Some((ordered_for, spot_price, ordered_by)) = R::order_placed(&record.event) {
+			if ordered_for == para_id && spot_price == amount && ordered_by == *payer {
+				return true
+			}
+		}
+	}
+
+	false
+}
+
+/// Encodes the events the way they are stored in `System::Events`.
+#[cfg(any(test, feature = "runtime-benchmarks"))]
+pub fn encode_events<Event: Encode>(records: Vec<(Phase, Event)>) -> Vec<u8> {
+	records
+		.into_iter()
+		.map(|(phase, event)| RelayEventRecord { phase, event, topics: Vec::new() })
+		.collect::<Vec<_>>()
+		.encode()
+}
+
+/// Builds a state root and a proof of the given key-value pairs.
+#[cfg(any(test, feature = "runtime-benchmarks"))]
+pub fn build_proof(entries: Vec<(Vec<u8>, Vec<u8>)>) -> (RelayHash, StorageProof) {
+	use sp_trie::{MemoryDB, TrieDBMutBuilder, TrieMut};
+
+	let mut db = MemoryDB::<BlakeTwo256>::default();
+	let mut root = Default::default();
+	{
+		let mut trie = TrieDBMutBuilder::<LayoutV1<BlakeTwo256>>::new(&mut db, &mut root).build();
+		for (key, value) in entries {
+			trie.insert(&key, &value).expect("Inserting into an in-memory trie works");
+		}
+	}
+
+	let nodes = db.drain().into_values().filter(|(_, rc)| *rc > 0).map(|(node, _)| node);
+	(root, StorageProof::new(nodes))
+}
+
+/// Builds a proof of an order placed in relay chain block `1`, returning it together with the
+/// state root of the relay parent.
+///
+/// The order is preceded by orders of another parachain, until the events take up at least
+/// `padding` bytes.
+#[cfg(any(test, feature = "runtime-benchmarks"))]
+pub fn build_order_proof<R, Balance>(
+	para_id: ParaId,
+	payer: AccountId32,
+	amount: Balance,
+	padding: usize,
+) -> (RelayHash, crate::OrderProof<Balance>)
+where
+	R: RelayEvents,
+	Balance: Copy + sp_runtime::traits::UniqueSaturatedInto<u128>,
+{
+	let extrinsic_index = 1;
+	let other_para = ParaId::new(u32::from(para_id).wrapping_add(1));
+	let filler = || {
+		(
+			Phase::ApplyExtrinsic(0),
+			R::order_placed_event(other_para, amount.unique_saturated_into(), payer.clone()),
+		)
+	};
+	let (phase, event) = filler();
+	let filler_size = RelayEventRecord { phase, event, topics: Vec::new() }.encoded_size();
+	let mut records: Vec<_> = (0..padding.div_ceil(filler_size)).map(|_| filler()).collect();
+	records.push((
+		Phase::ApplyExtrinsic(extrinsic_index),
+		R::order_placed_event(para_id, amount.unique_saturated_into(), payer.clone()),
+	));
+
+	build_events_proof(encode_events(records), extrinsic_index, payer, amount)
+}
+
+/// Builds a proof claiming an order in relay chain block `1` with the given encoded events,
+/// returning it together with the state root of the relay parent.
+#[cfg(any(test, feature = "runtime-benchmarks"))]
+pub fn build_events_proof<Balance>(
+	events: Vec<u8>,
+	extrinsic_index: u32,
+	payer: AccountId32,
+	amount: Balance,
+) -> (RelayHash, crate::OrderProof<Balance>) {
+	use cumulus_primitives_core::relay_chain::Header as RelayHeader;
+
+	let (state_root, events_proof) = build_proof(alloc::vec![(events_key(), events)]);
+
+	let header = RelayHeader {
+		parent_hash: Default::default(),
+		number: 1,
+		state_root,
+		extrinsics_root: Default::default(),
+		digest: Default::default(),
+	};
+	let (relay_parent_root, block_hash_proof) =
+		build_proof(alloc::vec![(block_hash_key(1), header.hash().encode())]);
+
+	let proof = crate::OrderProof {
+		header,
+		extrinsic_index,
+		payer,
+		amount,
+		block_hash_proof,
+		events_proof,
+	};
+	(relay_parent_root, proof)
+}
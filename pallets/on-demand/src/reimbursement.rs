@@ -0,0 +1,29 @@
+//! Funding of the pot from which collators get reimbursed for on-demand orders.
+
+use crate::{Config, CreditOf, Pallet, ReimbursementShare};
+use core::marker::PhantomData;
+use frame_support::traits::{
+	fungible::Balanced,
+	tokens::imbalance::{Imbalance, OnUnbalanced},
+};
+
+/// Moves `ReimbursementShare` of the handled credit into the reimbursement pot, passing the rest
+/// on to `OnRest`.
+///
+/// Meant to be used for handling transaction fees.
+pub struct FeeShare<T, OnRest>(PhantomData<(T, OnRest)>);
+
+impl<T: Config, OnRest: OnUnbalanced<CreditOf<T>>> OnUnbalanced<CreditOf<T>>
+	for FeeShare<T, OnRest>
+{
+	fn on_nonzero_unbalanced(credit: CreditOf<T>) {
+		let share = ReimbursementShare::<T>::get().mul_floor(credit.peek());
+		let (share, rest) = credit.split(share);
+
+		// The share can't be resolved if it is below the existential deposit of an empty pot.
+		if let Err(share) = T::Currency::resolve(&Pallet::<T>::pot(), share) {
+			OnRest::on_unbalanced(share);
+		}
+		OnRest::on_unbalanced(rest);
+	}
+}
@@ -1,9 +1,36 @@
 use crate::{
-	mock::{new_test_ext, OnDemand, RuntimeOrigin, System, Test},
-	Event, ThresholdParameter,
+	migration::v1::MigrateToV1,
+	mock::{
+		new_test_ext, Author, Authorities, AuthorityKeys, Balances, Now, OnDemand,
+		RelayBlockNumber, RelayChainEvents, RelayEvent, RelayOnDemandEvent, RelayStateRoot,
+		RuntimeOrigin, SelfParaId, System, Test,
+	},
+	proof::{build_events_proof, build_order_proof, encode_events},
+	ActiveProfileOffset, AdaptiveThreshold, AdaptiveThresholdConfig, Call, ChangeProfile,
+	ChangeProfileOf, Error, Event, FeeShare, InboundMessageThreshold, LastRelayParent,
+	LastSpotPrice, MaxIdleRelayBlocks, MaxOrderPrice, OrderBlocks, OrderRecord, Orders,
+	PaymentAccounts, ReimbursementShare, RelayScheduledChanges, ScheduledChange, ScheduledChangeOf,
+	ScheduledChanges, SlotWidth, SpotPricePayload, SpotPriceReport, Thresholds,
+};
+use codec::{Compact, Encode};
+use cumulus_primitives_core::ParaId;
+use frame::deps::frame_system::Phase;
+use frame_support::{
+	assert_noop, assert_ok,
+	dispatch::Pays,
+	traits::{
+		fungible::{Balanced, Inspect, Mutate},
+		GetStorageVersion, Hooks, OnRuntimeUpgrade, OnUnbalanced, StorageVersion,
+	},
+};
+use on_demand_primitives::{MessageThreshold, ThresholdKind};
+use sp_core::H256;
+use sp_runtime::{
+	testing::UintAuthorityId,
+	traits::{BadOrigin, ValidateUnsigned},
+	transaction_validity::{InvalidTransaction, TransactionSource},
+	AccountId32, DispatchError, DispatchResult, FixedU128, Perbill, RuntimeAppPublic,
 };
-use frame_support::{assert_noop, assert_ok};
-use sp_runtime::traits::BadOrigin;
 
 #[test]
 fn set_slot_width_works() {
@@ -27,7 +54,7 @@ fn set_slot_width_works() {
 #[test]
 fn set_threshold_parameter_works() {
 	new_test_ext().execute_with(|| {
-		assert_eq!(ThresholdParameter::<Test>::get(), 0);
+		assert_eq!(OnDemand::threshold_parameter(), 0);
 
 		// Failure: Bad origin
 		assert_noop!(OnDemand::set_threshold_parameter(RuntimeOrigin::signed(1), 1_000), BadOrigin);
@@ -36,9 +63,1037 @@ fn set_threshold_parameter_works() {
 		assert_ok!(OnDemand::set_threshold_parameter(RuntimeOrigin::root(), 1_000));
 
 		// Check the storage item
-		assert_eq!(ThresholdParameter::<Test>::get(), 1_000);
+		assert_eq!(OnDemand::threshold_parameter(), 1_000);
+		assert_eq!(OnDemand::threshold(ThresholdKind::Fees), Some(1_000));
 
 		// Check the emitted events
 		System::assert_last_event(Event::ThresholdParameterSet { parameter: 1_000 }.into());
 	})
 }
+
+#[test]
+fn set_max_order_price_works() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(MaxOrderPrice::<Test>::get(), None);
+
+		// Failure: Bad origin
+		assert_noop!(OnDemand::set_max_order_price(RuntimeOrigin::signed(1), Some(500)), BadOrigin);
+
+		// Should be working fine
+		assert_ok!(OnDemand::set_max_order_price(RuntimeOrigin::root(), Some(500)));
+
+		// Check the storage item
+		assert_eq!(MaxOrderPrice::<Test>::get(), Some(500));
+
+		// Check the emitted events
+		System::assert_last_event(Event::MaxOrderPriceSet { price: Some(500) }.into());
+
+		// Removing the limit works
+		assert_ok!(OnDemand::set_max_order_price(RuntimeOrigin::root(), None));
+		assert_eq!(MaxOrderPrice::<Test>::get(), None);
+		System::assert_last_event(Event::MaxOrderPriceSet { price: None }.into());
+	})
+}
+
+#[test]
+fn set_max_idle_relay_blocks_works() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(MaxIdleRelayBlocks::<Test>::get(), None);
+
+		// Failure: Bad origin
+		assert_noop!(
+			OnDemand::set_max_idle_relay_blocks(RuntimeOrigin::signed(1), Some(100)),
+			BadOrigin
+		);
+
+		// Should be working fine
+		assert_ok!(OnDemand::set_max_idle_relay_blocks(RuntimeOrigin::root(), Some(100)));
+
+		// Check the storage item
+		assert_eq!(MaxIdleRelayBlocks::<Test>::get(), Some(100));
+
+		// Check the emitted events
+		System::assert_last_event(Event::MaxIdleRelayBlocksSet { blocks: Some(100) }.into());
+
+		// Disabling forced orders works
+		assert_ok!(OnDemand::set_max_idle_relay_blocks(RuntimeOrigin::root(), None));
+		assert_eq!(MaxIdleRelayBlocks::<Test>::get(), None);
+		System::assert_last_event(Event::MaxIdleRelayBlocksSet { blocks: None }.into());
+	})
+}
+
+#[test]
+fn set_inbound_message_threshold_works() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(InboundMessageThreshold::<Test>::get(), None);
+
+		let threshold = MessageThreshold { count: 5, max_age: 20 };
+
+		// Failure: Bad origin
+		assert_noop!(
+			OnDemand::set_inbound_message_threshold(RuntimeOrigin::signed(1), Some(threshold)),
+			BadOrigin
+		);
+
+		// Should be working fine
+		assert_ok!(OnDemand::set_inbound_message_threshold(RuntimeOrigin::root(), Some(threshold)));
+
+		// Check the storage item
+		assert_eq!(InboundMessageThreshold::<Test>::get(), Some(threshold));
+
+		// Check the emitted events
+		System::assert_last_event(
+			Event::InboundMessageThresholdSet { threshold: Some(threshold) }.into(),
+		);
+
+		// Disabling the threshold works
+		assert_ok!(OnDemand::set_inbound_message_threshold(RuntimeOrigin::root(), None));
+		assert_eq!(InboundMessageThreshold::<Test>::get(), None);
+		System::assert_last_event(Event::InboundMessageThresholdSet { threshold: None }.into());
+	})
+}
+
+#[test]
+fn set_threshold_works() {
+	new_test_ext().execute_with(|| {
+		assert!(Thresholds::<Test>::get().is_empty());
+
+		// Failure: Bad origin
+		assert_noop!(
+			OnDemand::set_threshold(
+				RuntimeOrigin::signed(1),
+				ThresholdKind::TransactionCount,
+				Some(5)
+			),
+			BadOrigin
+		);
+
+		// Should be working fine
+		assert_ok!(OnDemand::set_threshold(
+			RuntimeOrigin::root(),
+			ThresholdKind::TransactionCount,
+			Some(5)
+		));
+		assert_ok!(OnDemand::set_threshold(RuntimeOrigin::root(), ThresholdKind::Price, Some(500)));
+
+		// Check the storage item
+		assert_eq!(OnDemand::threshold(ThresholdKind::TransactionCount), Some(5));
+		assert_eq!(OnDemand::threshold(ThresholdKind::Price), Some(500));
+		assert_eq!(OnDemand::threshold(ThresholdKind::Fees), None);
+
+		// Check the emitted events
+		System::assert_last_event(
+			Event::ThresholdSet { kind: ThresholdKind::Price, value: Some(500) }.into(),
+		);
+
+		// Removing a threshold works
+		assert_ok!(OnDemand::set_threshold(RuntimeOrigin::root(), ThresholdKind::Price, None));
+		assert_eq!(OnDemand::threshold(ThresholdKind::Price), None);
+		System::assert_last_event(
+			Event::ThresholdSet { kind: ThresholdKind::Price, value: None }.into(),
+		);
+
+		// Setting the threshold parameter kind also reports the new threshold parameter
+		assert_ok!(OnDemand::set_threshold(RuntimeOrigin::root(), ThresholdKind::Fees, Some(800)));
+		System::assert_has_event(
+			Event::ThresholdSet { kind: ThresholdKind::Fees, value: Some(800) }.into(),
+		);
+		System::assert_last_event(Event::ThresholdParameterSet { parameter: 800 }.into());
+	})
+}
+
+#[test]
+fn set_threshold_respects_max_thresholds() {
+	new_test_ext().execute_with(|| {
+		for i in 0..4 {
+			assert_ok!(OnDemand::set_threshold(
+				RuntimeOrigin::root(),
+				ThresholdKind::Custom(i),
+				Some(1)
+			));
+		}
+
+		// Failure: Too many thresholds
+		assert_noop!(
+			OnDemand::set_threshold(RuntimeOrigin::root(), ThresholdKind::Fees, Some(1)),
+			Error::<Test>::TooManyThresholds
+		);
+		assert_noop!(
+			OnDemand::set_threshold_parameter(RuntimeOrigin::root(), 1),
+			Error::<Test>::TooManyThresholds
+		);
+
+		// Updating an existing threshold still works
+		assert_ok!(OnDemand::set_threshold(
+			RuntimeOrigin::root(),
+			ThresholdKind::Custom(0),
+			Some(2)
+		));
+		assert_eq!(OnDemand::threshold(ThresholdKind::Custom(0)), Some(2));
+	})
+}
+
+#[test]
+fn migrate_to_v1_works() {
+	new_test_ext().execute_with(|| {
+		let old_key = frame_support::storage::storage_prefix(b"OnDemand", b"ThresholdParameter");
+		StorageVersion::new(0).put::<OnDemand>();
+		frame_support::storage::unhashed::put(&old_key, &1_000u64);
+
+		MigrateToV1::<Test>::on_runtime_upgrade();
+
+		assert_eq!(OnDemand::threshold(ThresholdKind::Fees), Some(1_000));
+		assert_eq!(OnDemand::threshold_parameter(), 1_000);
+		assert!(!frame_support::storage::unhashed::exists(&old_key));
+		assert_eq!(OnDemand::on_chain_storage_version(), 1);
+	})
+}
+
+#[test]
+fn schedule_change_works() {
+	new_test_ext().execute_with(|| {
+		let change = ScheduledChange::SlotWidth(2);
+
+		// Failure: Bad origin
+		assert_noop!(
+			OnDemand::schedule_change(RuntimeOrigin::signed(1), 10, change.clone()),
+			BadOrigin
+		);
+
+		// Failure: Scheduled in the past
+		assert_noop!(
+			OnDemand::schedule_change(RuntimeOrigin::root(), 1, change.clone()),
+			Error::<Test>::ScheduledInThePast
+		);
+
+		// Should be working fine
+		assert_ok!(OnDemand::schedule_change(RuntimeOrigin::root(), 10, change.clone()));
+		System::assert_last_event(
+			Event::ChangeScheduled { when: 10, change: change.clone() }.into(),
+		);
+
+		for _ in 1..4 {
+			assert_ok!(OnDemand::schedule_change(RuntimeOrigin::root(), 10, change.clone()));
+		}
+
+		// Failure: Too many scheduled changes
+		assert_noop!(
+			OnDemand::schedule_change(RuntimeOrigin::root(), 10, change.clone()),
+			Error::<Test>::TooManyScheduledChanges
+		);
+
+		// Cancelling works
+		assert_noop!(OnDemand::cancel_scheduled_changes(RuntimeOrigin::signed(1), 10), BadOrigin);
+		assert_ok!(OnDemand::cancel_scheduled_changes(RuntimeOrigin::root(), 10));
+		assert!(ScheduledChanges::<Test>::get(10).is_empty());
+		System::assert_last_event(Event::ScheduledChangesCancelled { when: 10 }.into());
+	})
+}
+
+#[test]
+fn scheduled_changes_get_applied() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(OnDemand::schedule_change(
+			RuntimeOrigin::root(),
+			5,
+			ScheduledChange::SlotWidth(2)
+		));
+		assert_ok!(OnDemand::schedule_change(
+			RuntimeOrigin::root(),
+			5,
+			ScheduledChange::ThresholdParameter(1_000)
+		));
+		assert_ok!(OnDemand::schedule_change(
+			RuntimeOrigin::root(),
+			8,
+			ScheduledChange::Threshold { kind: ThresholdKind::Price, value: Some(500) }
+		));
+
+		// Nothing changes before the scheduled block.
+		OnDemand::on_initialize(4);
+		assert_eq!(SlotWidth::<Test>::get(), 0);
+		assert_eq!(OnDemand::threshold_parameter(), 0);
+
+		System::set_block_number(5);
+		OnDemand::on_initialize(5);
+		assert_eq!(SlotWidth::<Test>::get(), 2);
+		assert_eq!(OnDemand::threshold_parameter(), 1_000);
+		assert!(ScheduledChanges::<Test>::get(5).is_empty());
+		System::assert_has_event(Event::SlotWidthSet { width: 2 }.into());
+		System::assert_last_event(Event::ThresholdParameterSet { parameter: 1_000 }.into());
+
+		System::set_block_number(8);
+		OnDemand::on_initialize(8);
+		assert_eq!(OnDemand::threshold(ThresholdKind::Price), Some(500));
+		System::assert_last_event(
+			Event::ThresholdSet { kind: ThresholdKind::Price, value: Some(500) }.into(),
+		);
+	})
+}
+
+#[test]
+fn failed_scheduled_change_is_reported() {
+	new_test_ext().execute_with(|| {
+		for i in 0..4 {
+			assert_ok!(OnDemand::set_threshold(
+				RuntimeOrigin::root(),
+				ThresholdKind::Custom(i),
+				Some(1)
+			));
+		}
+		let change = ScheduledChange::Threshold { kind: ThresholdKind::Fees, value: Some(1) };
+		assert_ok!(OnDemand::schedule_change(RuntimeOrigin::root(), 5, change.clone()));
+
+		System::set_block_number(5);
+		OnDemand::on_initialize(5);
+
+		assert_eq!(OnDemand::threshold(ThresholdKind::Fees), None);
+		System::assert_last_event(
+			Event::ScheduledChangeFailed {
+				change,
+				error: DispatchError::from(Error::<Test>::TooManyThresholds),
+			}
+			.into(),
+		);
+	})
+}
+
+#[test]
+fn schedule_relay_change_works() {
+	new_test_ext().execute_with(|| {
+		let change = ScheduledChange::SlotWidth(2);
+
+		// Failure: Bad origin
+		assert_noop!(
+			OnDemand::schedule_relay_change(RuntimeOrigin::signed(1), 10, change.clone()),
+			BadOrigin
+		);
+
+		// Failure: Scheduled in the past
+		assert_noop!(
+			OnDemand::schedule_relay_change(RuntimeOrigin::root(), 1, change.clone()),
+			Error::<Test>::ScheduledInThePast
+		);
+
+		// Should be working fine
+		assert_ok!(OnDemand::schedule_relay_change(RuntimeOrigin::root(), 10, change.clone()));
+		System::assert_last_event(
+			Event::RelayChangeScheduled { when: 10, change: change.clone() }.into(),
+		);
+
+		// Changes are kept ordered by relay chain block.
+		let other = ScheduledChange::ThresholdParameter(1_000);
+		assert_ok!(OnDemand::schedule_relay_change(RuntimeOrigin::root(), 5, other.clone()));
+		assert_ok!(OnDemand::schedule_relay_change(RuntimeOrigin::root(), 10, other.clone()));
+		assert_ok!(OnDemand::schedule_relay_change(RuntimeOrigin::root(), 7, change.clone()));
+		assert_eq!(
+			RelayScheduledChanges::<Test>::get().into_inner(),
+			vec![(5, other.clone()), (7, change.clone()), (10, change.clone()), (10, other)]
+		);
+
+		// Failure: Too many scheduled changes
+		assert_noop!(
+			OnDemand::schedule_relay_change(RuntimeOrigin::root(), 12, change.clone()),
+			Error::<Test>::TooManyScheduledChanges
+		);
+
+		// Cancelling works
+		assert_noop!(
+			OnDemand::cancel_relay_scheduled_changes(RuntimeOrigin::signed(1), 10),
+			BadOrigin
+		);
+		assert_ok!(OnDemand::cancel_relay_scheduled_changes(RuntimeOrigin::root(), 10));
+		assert_eq!(RelayScheduledChanges::<Test>::get().len(), 2);
+		System::assert_last_event(Event::RelayScheduledChangesCancelled { when: 10 }.into());
+	})
+}
+
+#[test]
+fn relay_scheduled_changes_get_applied() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(OnDemand::schedule_relay_change(
+			RuntimeOrigin::root(),
+			5,
+			ScheduledChange::SlotWidth(2)
+		));
+		assert_ok!(OnDemand::schedule_relay_change(
+			RuntimeOrigin::root(),
+			5,
+			ScheduledChange::ThresholdParameter(1_000)
+		));
+		assert_ok!(OnDemand::schedule_relay_change(
+			RuntimeOrigin::root(),
+			8,
+			ScheduledChange::Threshold { kind: ThresholdKind::Price, value: Some(500) }
+		));
+
+		// Nothing changes before the scheduled relay chain block.
+		RelayBlockNumber::set(4);
+		OnDemand::on_finalize(1);
+		assert_eq!(SlotWidth::<Test>::get(), 0);
+		assert_eq!(OnDemand::threshold_parameter(), 0);
+
+		RelayBlockNumber::set(5);
+		OnDemand::on_finalize(2);
+		assert_eq!(SlotWidth::<Test>::get(), 2);
+		assert_eq!(OnDemand::threshold_parameter(), 1_000);
+		assert_eq!(RelayScheduledChanges::<Test>::get().len(), 1);
+		System::assert_has_event(Event::SlotWidthSet { width: 2 }.into());
+		System::assert_last_event(Event::ThresholdParameterSet { parameter: 1_000 }.into());
+
+		// Changes of skipped relay chain blocks are applied by the next parachain block.
+		RelayBlockNumber::set(10);
+		OnDemand::on_finalize(3);
+		assert_eq!(OnDemand::threshold(ThresholdKind::Price), Some(500));
+		assert!(RelayScheduledChanges::<Test>::get().is_empty());
+	})
+}
+
+fn change_profile(entries: Vec<(u32, ScheduledChangeOf<Test>)>) -> ChangeProfileOf<Test> {
+	ChangeProfile { period: 100, entries: entries.try_into().unwrap() }
+}
+
+#[test]
+fn set_change_profile_works() {
+	new_test_ext().execute_with(|| {
+		let profile = change_profile(vec![
+			(10, ScheduledChange::SlotWidth(2)),
+			(60, ScheduledChange::SlotWidth(3)),
+		]);
+
+		// Failure: Bad origin
+		assert_noop!(
+			OnDemand::set_change_profile(RuntimeOrigin::signed(1), Some(profile.clone())),
+			BadOrigin
+		);
+
+		// Failure: No period
+		assert_noop!(
+			OnDemand::set_change_profile(
+				RuntimeOrigin::root(),
+				Some(ChangeProfile { period: 0, ..profile.clone() })
+			),
+			Error::<Test>::InvalidChangeProfile
+		);
+
+		// Failure: Offset outside of the period
+		assert_noop!(
+			OnDemand::set_change_profile(
+				RuntimeOrigin::root(),
+				Some(change_profile(vec![(100, ScheduledChange::SlotWidth(2))]))
+			),
+			Error::<Test>::InvalidChangeProfile
+		);
+
+		// Failure: Unordered offsets
+		assert_noop!(
+			OnDemand::set_change_profile(
+				RuntimeOrigin::root(),
+				Some(change_profile(vec![
+					(60, ScheduledChange::SlotWidth(3)),
+					(10, ScheduledChange::SlotWidth(2)),
+				]))
+			),
+			Error::<Test>::InvalidChangeProfile
+		);
+
+		// Should be working fine
+		assert_ok!(OnDemand::set_change_profile(RuntimeOrigin::root(), Some(profile.clone())));
+		assert_eq!(OnDemand::change_profile(), Some(profile.clone()));
+		System::assert_last_event(Event::ChangeProfileSet { profile: Some(profile) }.into());
+
+		// Removing works
+		assert_ok!(OnDemand::set_change_profile(RuntimeOrigin::root(), None));
+		assert_eq!(OnDemand::change_profile(), None);
+		System::assert_last_event(Event::ChangeProfileSet { profile: None }.into());
+	})
+}
+
+#[test]
+fn change_profile_gets_applied() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(OnDemand::set_change_profile(
+			RuntimeOrigin::root(),
+			Some(change_profile(vec![
+				(10, ScheduledChange::SlotWidth(2)),
+				(10, ScheduledChange::ThresholdParameter(1_000)),
+				(60, ScheduledChange::SlotWidth(3)),
+			]))
+		));
+
+		// Before the first offset, the last entries of the previous period are in effect.
+		Now::set(205);
+		OnDemand::on_finalize(1);
+		assert_eq!(SlotWidth::<Test>::get(), 3);
+		assert_eq!(ActiveProfileOffset::<Test>::get(), Some(60));
+
+		// All the entries of an offset are applied once.
+		Now::set(210);
+		OnDemand::on_finalize(2);
+		assert_eq!(SlotWidth::<Test>::get(), 2);
+		assert_eq!(OnDemand::threshold_parameter(), 1_000);
+		System::assert_last_event(Event::ThresholdParameterSet { parameter: 1_000 }.into());
+
+		System::reset_events();
+		assert_ok!(OnDemand::set_slot_width(RuntimeOrigin::root(), 4));
+		Now::set(259);
+		OnDemand::on_finalize(3);
+		assert_eq!(SlotWidth::<Test>::get(), 4);
+
+		Now::set(260);
+		OnDemand::on_finalize(4);
+		assert_eq!(SlotWidth::<Test>::get(), 3);
+	})
+}
+
+fn adaptive_threshold() -> AdaptiveThreshold<u64> {
+	AdaptiveThreshold { margin: FixedU128::from_rational(3, 2), min: 1_000, max: 10_000 }
+}
+
+fn spot_price_report(price: u64, authority: u64) -> Call<Test> {
+	let payload = SpotPricePayload {
+		price,
+		block_number: System::block_number(),
+		authority: UintAuthorityId(authority),
+	};
+	let signature = payload.using_encoded(|encoded| payload.authority.sign(&encoded)).unwrap();
+	Call::report_spot_price { payload, signature }
+}
+
+fn report_spot_price(price: u64) -> DispatchResult {
+	let Call::report_spot_price { payload, signature } = spot_price_report(price, 1) else {
+		unreachable!()
+	};
+	OnDemand::report_spot_price(RuntimeOrigin::none(), payload, signature)
+}
+
+#[test]
+fn set_adaptive_threshold_works() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(AdaptiveThresholdConfig::<Test>::get(), None);
+
+		// Failure: Bad origin
+		assert_noop!(
+			OnDemand::set_adaptive_threshold(RuntimeOrigin::signed(1), Some(adaptive_threshold())),
+			BadOrigin
+		);
+
+		// Failure: Invalid bounds
+		let invalid = AdaptiveThreshold { min: 10_001, ..adaptive_threshold() };
+		assert_noop!(
+			OnDemand::set_adaptive_threshold(RuntimeOrigin::root(), Some(invalid)),
+			Error::<Test>::InvalidThresholdBounds
+		);
+
+		// Should be working fine
+		assert_ok!(OnDemand::set_adaptive_threshold(
+			RuntimeOrigin::root(),
+			Some(adaptive_threshold())
+		));
+
+		// Check the storage item
+		assert_eq!(AdaptiveThresholdConfig::<Test>::get(), Some(adaptive_threshold()));
+
+		// Check the emitted events
+		System::assert_last_event(
+			Event::AdaptiveThresholdSet { config: Some(adaptive_threshold()) }.into(),
+		);
+	})
+}
+
+#[test]
+fn report_spot_price_adjusts_threshold() {
+	new_test_ext().execute_with(|| {
+		// Failure: Signed origin
+		let Call::report_spot_price { payload, signature } = spot_price_report(2_000, 1) else {
+			unreachable!()
+		};
+		assert_noop!(
+			OnDemand::report_spot_price(RuntimeOrigin::signed(1), payload, signature),
+			BadOrigin
+		);
+
+		// Failure: Adaptive threshold disabled
+		assert_noop!(report_spot_price(2_000), Error::<Test>::AdaptiveThresholdDisabled);
+
+		assert_ok!(OnDemand::set_adaptive_threshold(
+			RuntimeOrigin::root(),
+			Some(adaptive_threshold())
+		));
+
+		// Should be working fine
+		assert_ok!(report_spot_price(2_000));
+		assert_eq!(OnDemand::threshold_parameter(), 3_000);
+		assert_eq!(
+			LastSpotPrice::<Test>::get(),
+			Some(SpotPriceReport { price: 2_000, reported_at: 1 })
+		);
+		System::assert_has_event(Event::SpotPriceReported { price: 2_000 }.into());
+		System::assert_last_event(Event::ThresholdParameterSet { parameter: 3_000 }.into());
+
+		// Failure: Already reported in this block
+		assert_noop!(report_spot_price(2_000), Error::<Test>::SpotPriceAlreadyReported);
+
+		// The threshold stays within the bounds.
+		System::set_block_number(2);
+		assert_ok!(report_spot_price(100_000));
+		assert_eq!(OnDemand::threshold_parameter(), 10_000);
+
+		System::set_block_number(3);
+		assert_ok!(report_spot_price(10));
+		assert_eq!(OnDemand::threshold_parameter(), 1_000);
+	})
+}
+
+#[test]
+fn validate_unsigned_accepts_one_report_per_block() {
+	new_test_ext().execute_with(|| {
+		Authorities::set(vec![1]);
+		let call = spot_price_report(2_000, 1);
+
+		assert_eq!(
+			OnDemand::validate_unsigned(TransactionSource::External, &call),
+			InvalidTransaction::Stale.into()
+		);
+
+		assert_ok!(OnDemand::set_adaptive_threshold(
+			RuntimeOrigin::root(),
+			Some(adaptive_threshold())
+		));
+		assert_ok!(OnDemand::validate_unsigned(TransactionSource::External, &call));
+
+		assert_ok!(report_spot_price(2_000));
+		assert_eq!(
+			OnDemand::validate_unsigned(TransactionSource::External, &call),
+			InvalidTransaction::Stale.into()
+		);
+
+		System::set_block_number(2);
+		assert_ok!(OnDemand::validate_unsigned(
+			TransactionSource::External,
+			&spot_price_report(2_000, 1)
+		));
+
+		assert_eq!(
+			OnDemand::validate_unsigned(
+				TransactionSource::External,
+				&Call::<Test>::set_slot_width { width: 1 }
+			),
+			InvalidTransaction::Call.into()
+		);
+	})
+}
+
+#[test]
+fn validate_unsigned_authenticates_reports() {
+	new_test_ext().execute_with(|| {
+		Authorities::set(vec![1]);
+		assert_ok!(OnDemand::set_adaptive_threshold(
+			RuntimeOrigin::root(),
+			Some(adaptive_threshold())
+		));
+
+		// Failure: Not an authority
+		assert_eq!(
+			OnDemand::validate_unsigned(TransactionSource::External, &spot_price_report(2_000, 2)),
+			InvalidTransaction::BadSigner.into()
+		);
+
+		// Failure: The payload doesn't match the signature
+		let Call::report_spot_price { mut payload, signature } = spot_price_report(2_000, 1) else {
+			unreachable!()
+		};
+		payload.price = 1_000;
+		assert_eq!(
+			OnDemand::validate_unsigned(
+				TransactionSource::External,
+				&Call::report_spot_price { payload, signature }
+			),
+			InvalidTransaction::BadProof.into()
+		);
+
+		// Failure: Meant for another block
+		let report = spot_price_report(2_000, 1);
+		System::set_block_number(2);
+		assert_eq!(
+			OnDemand::validate_unsigned(TransactionSource::External, &report),
+			InvalidTransaction::Stale.into()
+		);
+		System::set_block_number(0);
+		assert_eq!(
+			OnDemand::validate_unsigned(TransactionSource::External, &report),
+			InvalidTransaction::Future.into()
+		);
+	})
+}
+
+#[test]
+fn signed_spot_price_report_uses_authority_keys() {
+	new_test_ext().execute_with(|| {
+		UintAuthorityId::set_all_keys(vec![1, 2]);
+		Authorities::set(vec![2]);
+		assert_eq!(OnDemand::signed_spot_price_report(2_000), None);
+
+		assert_ok!(OnDemand::set_adaptive_threshold(
+			RuntimeOrigin::root(),
+			Some(adaptive_threshold())
+		));
+		let call = OnDemand::signed_spot_price_report(2_000).unwrap();
+		let Call::report_spot_price { payload, .. } = &call else { unreachable!() };
+		assert_eq!(
+			payload,
+			&SpotPricePayload { price: 2_000, block_number: 2, authority: UintAuthorityId(2) }
+		);
+
+		// The report is valid in the next block.
+		System::set_block_number(2);
+		assert_ok!(OnDemand::validate_unsigned(TransactionSource::External, &call));
+
+		Authorities::set(vec![3]);
+		assert_eq!(OnDemand::signed_spot_price_report(2_000), None);
+	})
+}
+
+#[test]
+fn record_order_works() {
+	new_test_ext().execute_with(|| {
+		let payer = AccountId32::new([1; 32]);
+		let (root, proof) =
+			build_order_proof::<RelayChainEvents, _>(SelfParaId::get(), payer.clone(), 500, 64);
+		RelayStateRoot::set(root);
+
+		// Failure: Bad origin
+		assert_noop!(
+			OnDemand::record_order(RuntimeOrigin::signed(1), Box::new(proof.clone())),
+			BadOrigin
+		);
+
+		assert_ok!(OnDemand::record_order(RuntimeOrigin::none(), Box::new(proof.clone())));
+		assert_eq!(
+			Orders::<Test>::get(1, 1),
+			Some(OrderRecord { payer: payer.clone(), amount: 500, reimbursed: false })
+		);
+		System::assert_last_event(
+			Event::OrderRecorded { payer, amount: 500, relay_block: 1 }.into(),
+		);
+
+		// Failure: Orders can only be recorded once
+		assert_noop!(
+			OnDemand::record_order(RuntimeOrigin::none(), Box::new(proof)),
+			Error::<Test>::OrderAlreadyRecorded
+		);
+	})
+}
+
+#[test]
+fn record_order_rejects_invalid_proofs() {
+	new_test_ext().execute_with(|| {
+		let payer = AccountId32::new([1; 32]);
+		let (root, proof) =
+			build_order_proof::<RelayChainEvents, _>(SelfParaId::get(), payer.clone(), 500, 0);
+
+		// Failure: The block isn't known to the relay parent
+		assert_noop!(
+			OnDemand::record_order(RuntimeOrigin::none(), Box::new(proof.clone())),
+			Error::<Test>::UnknownRelayBlock
+		);
+
+		RelayStateRoot::set(root);
+
+		// Failure: The claimed amount differs from the order
+		let mut wrong_amount = proof.clone();
+		wrong_amount.amount = 1_000;
+		assert_noop!(
+			OnDemand::record_order(RuntimeOrigin::none(), Box::new(wrong_amount)),
+			Error::<Test>::OrderNotFound
+		);
+
+		// Failure: The claimed payer differs from the order
+		let mut wrong_payer = proof.clone();
+		wrong_payer.payer = AccountId32::new([2; 32]);
+		assert_noop!(
+			OnDemand::record_order(RuntimeOrigin::none(), Box::new(wrong_payer)),
+			Error::<Test>::OrderNotFound
+		);
+
+		// Failure: The events are proven against a different state root
+		let mut wrong_header = proof.clone();
+		wrong_header.header.state_root = Default::default();
+		assert_noop!(
+			OnDemand::record_order(RuntimeOrigin::none(), Box::new(wrong_header)),
+			Error::<Test>::UnknownRelayBlock
+		);
+
+		// Failure: The order was placed for a different parachain
+		let (root, other_para) =
+			build_order_proof::<RelayChainEvents, _>(ParaId::new(3000), payer, 500, 0);
+		RelayStateRoot::set(root);
+		assert_noop!(
+			OnDemand::record_order(RuntimeOrigin::none(), Box::new(other_para)),
+			Error::<Test>::OrderNotFound
+		);
+	})
+}
+
+#[test]
+fn record_order_ignores_orders_within_other_events() {
+	new_test_ext().execute_with(|| {
+		let payer = AccountId32::new([1; 32]);
+		let order = RelayEvent::OnDemand(RelayOnDemandEvent::OnDemandOrderPlaced {
+			para_id: SelfParaId::get(),
+			spot_price: 500,
+			ordered_by: payer.clone(),
+		});
+
+		// The encoding of a valid order record, carried as the payload of another event.
+		let forged = encode_events(vec![(Phase::ApplyExtrinsic(1), order)]);
+		let events = encode_events(vec![
+			(Phase::ApplyExtrinsic(1), RelayEvent::Payload(forged[1..].to_vec())),
+			(
+				Phase::ApplyExtrinsic(1),
+				RelayEvent::OnDemand(RelayOnDemandEvent::SpotPriceSet { spot_price: 500 }),
+			),
+		]);
+		let (root, proof) = build_events_proof(events, 1, payer, 500);
+		RelayStateRoot::set(root);
+
+		assert_noop!(
+			OnDemand::record_order(RuntimeOrigin::none(), Box::new(proof)),
+			Error::<Test>::OrderNotFound
+		);
+	})
+}
+
+#[test]
+fn record_order_rejects_undecodable_events() {
+	new_test_ext().execute_with(|| {
+		let payer = AccountId32::new([1; 32]);
+		let order = RelayEvent::OnDemand(RelayOnDemandEvent::OnDemandOrderPlaced {
+			para_id: SelfParaId::get(),
+			spot_price: 500,
+			ordered_by: payer.clone(),
+		});
+
+		// An event of an unknown pallet precedes the order.
+		let mut events = Compact(2u32).encode();
+		events.extend((Phase::ApplyExtrinsic(0), 99u8, 0u8, Vec::<H256>::new()).encode());
+		events.extend((Phase::ApplyExtrinsic(1), order, Vec::<H256>::new()).encode());
+		let (root, proof) = build_events_proof(events, 1, payer, 500);
+		RelayStateRoot::set(root);
+
+		assert_noop!(
+			OnDemand::record_order(RuntimeOrigin::none(), Box::new(proof)),
+			Error::<Test>::OrderNotFound
+		);
+	})
+}
+
+#[test]
+fn validate_unsigned_accepts_valid_order_proofs() {
+	new_test_ext().execute_with(|| {
+		let (root, proof) = build_order_proof::<RelayChainEvents, _>(
+			SelfParaId::get(),
+			AccountId32::new([1; 32]),
+			500,
+			0,
+		);
+		let call = Call::<Test>::record_order { proof: Box::new(proof.clone()) };
+
+		assert_eq!(
+			OnDemand::validate_unsigned(TransactionSource::External, &call),
+			InvalidTransaction::BadProof.into()
+		);
+
+		RelayStateRoot::set(root);
+		assert_ok!(OnDemand::validate_unsigned(TransactionSource::External, &call));
+
+		assert_ok!(OnDemand::record_order(RuntimeOrigin::none(), Box::new(proof)));
+		assert_eq!(
+			OnDemand::validate_unsigned(TransactionSource::External, &call),
+			InvalidTransaction::Stale.into()
+		);
+	})
+}
+
+#[test]
+fn expired_orders_get_pruned() {
+	new_test_ext().execute_with(|| {
+		let (root, proof) = build_order_proof::<RelayChainEvents, _>(
+			SelfParaId::get(),
+			AccountId32::new([1; 32]),
+			500,
+			0,
+		);
+		RelayStateRoot::set(root);
+		assert_ok!(OnDemand::record_order(RuntimeOrigin::none(), Box::new(proof.clone())));
+		assert_eq!(OrderBlocks::<Test>::get().to_vec(), vec![1]);
+
+		// Orders are kept for the retention period.
+		RelayBlockNumber::set(11);
+		OnDemand::on_initialize(2);
+		assert!(Orders::<Test>::contains_key(1, 1));
+
+		RelayBlockNumber::set(12);
+		OnDemand::on_initialize(3);
+		assert!(!Orders::<Test>::contains_key(1, 1));
+		assert!(OrderBlocks::<Test>::get().is_empty());
+
+		// Failure: Expired orders can't be recorded again
+		assert_noop!(
+			OnDemand::record_order(RuntimeOrigin::none(), Box::new(proof.clone())),
+			Error::<Test>::OrderExpired
+		);
+		assert_eq!(
+			OnDemand::validate_unsigned(
+				TransactionSource::External,
+				&Call::<Test>::record_order { proof: Box::new(proof) }
+			),
+			InvalidTransaction::Stale.into()
+		);
+	})
+}
+
+#[test]
+fn set_reimbursement_share_works() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(ReimbursementShare::<Test>::get(), Perbill::zero());
+
+		// Failure: Bad origin
+		assert_noop!(
+			OnDemand::set_reimbursement_share(RuntimeOrigin::signed(1), Perbill::from_percent(20)),
+			BadOrigin
+		);
+
+		assert_ok!(OnDemand::set_reimbursement_share(
+			RuntimeOrigin::root(),
+			Perbill::from_percent(20)
+		));
+		assert_eq!(ReimbursementShare::<Test>::get(), Perbill::from_percent(20));
+		System::assert_last_event(
+			Event::ReimbursementShareSet { share: Perbill::from_percent(20) }.into(),
+		);
+	})
+}
+
+#[test]
+fn fee_share_funds_pot() {
+	new_test_ext().execute_with(|| {
+		// Without a share all fees are passed on.
+		FeeShare::<Test, ()>::on_unbalanced(Balances::issue(100));
+		assert_eq!(Balances::balance(&OnDemand::pot()), 0);
+
+		assert_ok!(OnDemand::set_reimbursement_share(
+			RuntimeOrigin::root(),
+			Perbill::from_percent(20)
+		));
+		FeeShare::<Test, ()>::on_unbalanced(Balances::issue(100));
+		assert_eq!(Balances::balance(&OnDemand::pot()), 20);
+		assert_eq!(Balances::total_issuance(), 20);
+	})
+}
+
+#[test]
+fn claim_reimbursement_works() {
+	new_test_ext().execute_with(|| {
+		let payer = AccountId32::new([1; 32]);
+		let who = 7;
+		AuthorityKeys::set(vec![(who, 7)]);
+		PaymentAccounts::<Test>::insert(UintAuthorityId(7), payer.clone());
+		let (root, proof) =
+			build_order_proof::<RelayChainEvents, _>(SelfParaId::get(), payer.clone(), 500, 0);
+		RelayStateRoot::set(root);
+		assert_ok!(OnDemand::record_order(RuntimeOrigin::none(), Box::new(proof)));
+
+		// Failure: Not the block author
+		assert_noop!(
+			OnDemand::claim_reimbursement(RuntimeOrigin::signed(who), 1, 1),
+			Error::<Test>::NotBlockAuthor
+		);
+
+		// Failure: Not the payer of the order
+		Author::set(Some(2));
+		assert_noop!(
+			OnDemand::claim_reimbursement(RuntimeOrigin::signed(2), 1, 1),
+			Error::<Test>::NotOrderPayer
+		);
+
+		// Failure: The order was placed before the relay parent of the previous block
+		Author::set(Some(who));
+		LastRelayParent::<Test>::set(Some(1));
+		assert_noop!(
+			OnDemand::claim_reimbursement(RuntimeOrigin::signed(who), 1, 1),
+			Error::<Test>::OrderNotForBlock
+		);
+		LastRelayParent::<Test>::set(Some(0));
+
+		// Failure: Unknown order
+		Author::set(Some(who));
+		assert_noop!(
+			OnDemand::claim_reimbursement(RuntimeOrigin::signed(who), 1, 2),
+			Error::<Test>::UnknownOrder
+		);
+
+		// Failure: The pot can't cover the order
+		assert_noop!(
+			OnDemand::claim_reimbursement(RuntimeOrigin::signed(who), 1, 1),
+			Error::<Test>::InsufficientPot
+		);
+
+		assert_ok!(Balances::mint_into(&OnDemand::pot(), 1_000));
+		let info = OnDemand::claim_reimbursement(RuntimeOrigin::signed(who), 1, 1).unwrap();
+		assert_eq!(info.pays_fee, Pays::No);
+		assert_eq!(Balances::balance(&who), 500);
+		assert_eq!(Balances::balance(&OnDemand::pot()), 500);
+		assert!(Orders::<Test>::get(1, 1).unwrap().reimbursed);
+		System::assert_last_event(
+			Event::OrderReimbursed { who, relay_block: 1, amount: 500 }.into(),
+		);
+
+		// Failure: Only one reimbursement per block
+		assert_noop!(
+			OnDemand::claim_reimbursement(RuntimeOrigin::signed(who), 1, 1),
+			Error::<Test>::ReimbursementAlreadyClaimed
+		);
+
+		// Failure: The order was already reimbursed
+		OnDemand::on_finalize(1);
+		assert_eq!(LastRelayParent::<Test>::get(), Some(1));
+		System::set_block_number(2);
+		assert_noop!(
+			OnDemand::claim_reimbursement(RuntimeOrigin::signed(who), 1, 1),
+			Error::<Test>::AlreadyReimbursed
+		);
+	})
+}
+
+#[test]
+fn set_payment_account_works() {
+	new_test_ext().execute_with(|| {
+		let payer = AccountId32::new([1; 32]);
+
+		// Failure: Bad origin
+		assert_noop!(
+			OnDemand::set_payment_account(RuntimeOrigin::none(), Some(payer.clone())),
+			BadOrigin
+		);
+
+		// Failure: No authority key
+		assert_noop!(
+			OnDemand::set_payment_account(RuntimeOrigin::signed(1), Some(payer.clone())),
+			Error::<Test>::NoAuthorityKey
+		);
+
+		AuthorityKeys::set(vec![(1, 10)]);
+		assert_ok!(OnDemand::set_payment_account(RuntimeOrigin::signed(1), Some(payer.clone())));
+		assert_eq!(PaymentAccounts::<Test>::get(UintAuthorityId(10)), Some(payer.clone()));
+		assert_eq!(OnDemand::payer_of(&UintAuthorityId(10)), Some(payer.clone()));
+		System::assert_last_event(
+			Event::PaymentAccountSet { authority: UintAuthorityId(10), payer: Some(payer) }.into(),
+		);
+
+		assert_ok!(OnDemand::set_payment_account(RuntimeOrigin::signed(1), None));
+		assert_eq!(PaymentAccounts::<Test>::get(UintAuthorityId(10)), None);
+		// `UintAuthorityId` isn't an account, so there is no fallback.
+		assert_eq!(OnDemand::payer_of(&UintAuthorityId(10)), None);
+	})
+}
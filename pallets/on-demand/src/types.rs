@@ -0,0 +1,120 @@
+use codec::{Decode, Encode, MaxEncodedLen};
+use cumulus_primitives_core::relay_chain::{
+	BlockNumber as RelayBlockNumber, Header as RelayHeader,
+};
+use frame::prelude::*;
+use frame_support::traits::fungible;
+use on_demand_primitives::ThresholdKind;
+use scale_info::TypeInfo;
+use sp_runtime::{AccountId32, FixedU128};
+use sp_trie::StorageProof;
+
+/// A configuration change which can be scheduled for a future block.
+#[derive(Encode, Decode, Debug, Clone, PartialEq, Eq, TypeInfo, MaxEncodedLen)]
+pub enum ScheduledChange<BlockNumber, ThresholdParameter> {
+	/// Sets the slot width.
+	SlotWidth(BlockNumber),
+	/// Sets the threshold parameter.
+	ThresholdParameter(ThresholdParameter),
+	/// Sets or removes the threshold of the given kind.
+	Threshold { kind: ThresholdKind, value: Option<ThresholdParameter> },
+}
+
+pub type ScheduledChangeOf<T> =
+	ScheduledChange<<T as crate::Config>::BlockNumber, <T as crate::Config>::ThresholdParameter>;
+
+/// The changes scheduled for a single block.
+pub type ScheduledChangesOf<T> =
+	BoundedVec<ScheduledChangeOf<T>, <T as crate::Config>::MaxScheduledPerBlock>;
+
+/// The changes scheduled for relay chain blocks, ordered by relay chain block.
+pub type RelayScheduledChangesOf<T> =
+	BoundedVec<(RelayBlockNumber, ScheduledChangeOf<T>), <T as crate::Config>::MaxRelayScheduled>;
+
+/// Configuration changes recurring every period, e.g. to follow the daily traffic.
+#[derive(Encode, Decode, Debug, Clone, PartialEq, Eq, TypeInfo, MaxEncodedLen)]
+pub struct ChangeProfile<Entries> {
+	/// The length of the period in seconds, e.g. `86_400` for a daily profile.
+	pub period: u32,
+	/// The changes with their offset into the period in seconds, ordered by offset.
+	///
+	/// The changes of an offset stay in effect until the next offset is reached.
+	pub entries: Entries,
+}
+
+pub type ChangeProfileOf<T> =
+	ChangeProfile<BoundedVec<(u32, ScheduledChangeOf<T>), <T as crate::Config>::MaxProfileEntries>>;
+
+/// Configures how the threshold parameter follows the on-demand spot price.
+#[derive(Encode, Decode, Debug, Clone, PartialEq, Eq, TypeInfo, MaxEncodedLen)]
+pub struct AdaptiveThreshold<Balance> {
+	/// The factor by which the collected fees should exceed the spot price.
+	pub margin: FixedU128,
+	/// The lower bound of the threshold, in relay chain balance.
+	pub min: Balance,
+	/// The upper bound of the threshold, in relay chain balance.
+	pub max: Balance,
+}
+
+/// The last on-demand spot price reported by a collator.
+#[derive(Encode, Decode, Debug, Clone, PartialEq, Eq, TypeInfo, MaxEncodedLen)]
+pub struct SpotPriceReport<Balance, BlockNumber> {
+	/// The reported spot price.
+	pub price: Balance,
+	/// The parachain block in which the price was reported.
+	pub reported_at: BlockNumber,
+}
+
+/// An on-demand spot price report of a collator authority.
+#[derive(Encode, Decode, Debug, Clone, PartialEq, Eq, TypeInfo)]
+pub struct SpotPricePayload<Balance, BlockNumber, AuthorityId> {
+	/// The reported spot price.
+	pub price: Balance,
+	/// The parachain block the report is meant for.
+	pub block_number: BlockNumber,
+	/// The authority reporting the price.
+	pub authority: AuthorityId,
+}
+
+pub type SpotPricePayloadOf<T> = SpotPricePayload<
+	<T as crate::Config>::Balance,
+	BlockNumberFor<T>,
+	<T as crate::Config>::AuthorityId,
+>;
+
+/// Proof that an on-demand order for this parachain got placed on the relay chain.
+#[derive(Encode, Decode, Debug, Clone, PartialEq, Eq, TypeInfo)]
+pub struct OrderProof<Balance> {
+	/// Header of the relay chain block containing the order.
+	pub header: RelayHeader,
+	/// Index of the order extrinsic within the relay chain block.
+	pub extrinsic_index: u32,
+	/// The relay chain account which paid for the order.
+	pub payer: AccountId32,
+	/// The amount paid for the order.
+	pub amount: Balance,
+	/// Proof of the block hash against the state of the relay parent.
+	pub block_hash_proof: StorageProof,
+	/// Proof of the events against the state of the block containing the order.
+	pub events_proof: StorageProof,
+}
+
+/// An on-demand order recorded on-chain.
+#[derive(Encode, Decode, Debug, Clone, PartialEq, Eq, TypeInfo, MaxEncodedLen)]
+pub struct OrderRecord<Balance> {
+	/// The relay chain account which paid for the order.
+	pub payer: AccountId32,
+	/// The amount paid for the order.
+	pub amount: Balance,
+	/// Whether the payer got reimbursed for the order.
+	pub reimbursed: bool,
+}
+
+/// The balance of the currency used for reimbursements.
+pub type CurrencyBalanceOf<T> = <<T as crate::Config>::Currency as fungible::Inspect<
+	<T as frame_system::Config>::AccountId,
+>>::Balance;
+
+/// Credit of the currency used for reimbursements.
+pub type CreditOf<T> =
+	fungible::Credit<<T as frame_system::Config>::AccountId, <T as crate::Config>::Currency>;
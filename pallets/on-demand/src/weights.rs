@@ -2,7 +2,7 @@
 //! Autogenerated weights for `pallet_on_demand`
 //!
 //! THIS FILE WAS AUTO-GENERATED USING THE SUBSTRATE BENCHMARK CLI VERSION 43.0.0
-//! DATE: 2024-12-24, STEPS: `20`, REPEAT: `50`, LOW RANGE: `[]`, HIGH RANGE: `[]`
+//! DATE: 2026-10-19, STEPS: `20`, REPEAT: `50`, LOW RANGE: `[]`, HIGH RANGE: `[]`
 //! WORST CASE MAP SIZE: `1000000`
 //! HOSTNAME: `sergej-B650-AORUS-ELITE-AX`, CPU: `AMD Ryzen 9 7900X3D 12-Core Processor`
 //! WASM-EXECUTION: `Compiled`, CHAIN: `None`, DB CACHE: `1024`
@@ -38,6 +38,24 @@ use core::marker::PhantomData;
 pub trait WeightInfo {
 	fn set_slot_width() -> Weight;
 	fn set_threshold_parameter() -> Weight;
+	fn set_max_order_price() -> Weight;
+	fn set_max_idle_relay_blocks() -> Weight;
+	fn set_inbound_message_threshold() -> Weight;
+	fn set_threshold() -> Weight;
+	fn schedule_change() -> Weight;
+	fn cancel_scheduled_changes() -> Weight;
+	fn on_initialize(c: u32, ) -> Weight;
+	fn set_adaptive_threshold() -> Weight;
+	fn report_spot_price() -> Weight;
+	fn record_order(p: u32, ) -> Weight;
+	fn set_reimbursement_share() -> Weight;
+	fn claim_reimbursement() -> Weight;
+	fn prune_orders(o: u32, ) -> Weight;
+	fn set_payment_account() -> Weight;
+	fn schedule_relay_change() -> Weight;
+	fn cancel_relay_scheduled_changes() -> Weight;
+	fn set_change_profile() -> Weight;
+	fn on_finalize(r: u32, p: u32, ) -> Weight;
 }
 
 /// Weights for `pallet_on_demand` using the Substrate node and recommended hardware.
@@ -53,16 +71,280 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 		Weight::from_parts(3_497_000, 0)
 			.saturating_add(T::DbWeight::get().writes(1_u64))
 	}
-	/// Storage: `OnDemand::ThresholdParameter` (r:0 w:1)
-	/// Proof: `OnDemand::ThresholdParameter` (`max_values`: Some(1), `max_size`: Some(16), added: 511, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::Thresholds` (r:1 w:1)
+	/// Proof: `OnDemand::Thresholds` (`max_values`: Some(1), `max_size`: Some(145), added: 640, mode: `MaxEncodedLen`)
 	fn set_threshold_parameter() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `127`
+		//  Estimated: `1630`
+		// Minimum execution time: 6_893_000 picoseconds.
+		Weight::from_parts(7_104_000, 1630)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `OnDemand::MaxOrderPrice` (r:0 w:1)
+	/// Proof: `OnDemand::MaxOrderPrice` (`max_values`: Some(1), `max_size`: Some(16), added: 511, mode: `MaxEncodedLen`)
+	fn set_max_order_price() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `0`
+		//  Estimated: `0`
+		// Minimum execution time: 3_316_000 picoseconds.
+		Weight::from_parts(3_492_000, 0)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `OnDemand::MaxIdleRelayBlocks` (r:0 w:1)
+	/// Proof: `OnDemand::MaxIdleRelayBlocks` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	fn set_max_idle_relay_blocks() -> Weight {
 		// Proof Size summary in bytes:
 		//  Measured:  `0`
 		//  Estimated: `0`
-		// Minimum execution time: 3_326_000 picoseconds.
-		Weight::from_parts(3_487_000, 0)
+		// Minimum execution time: 3_276_000 picoseconds.
+		Weight::from_parts(3_451_000, 0)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `OnDemand::InboundMessageThreshold` (r:0 w:1)
+	/// Proof: `OnDemand::InboundMessageThreshold` (`max_values`: Some(1), `max_size`: Some(8), added: 503, mode: `MaxEncodedLen`)
+	fn set_inbound_message_threshold() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `0`
+		//  Estimated: `0`
+		// Minimum execution time: 3_327_000 picoseconds.
+		Weight::from_parts(3_508_000, 0)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `OnDemand::Thresholds` (r:1 w:1)
+	/// Proof: `OnDemand::Thresholds` (`max_values`: Some(1), `max_size`: Some(145), added: 640, mode: `MaxEncodedLen`)
+	fn set_threshold() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `127`
+		//  Estimated: `1630`
+		// Minimum execution time: 6_958_000 picoseconds.
+		Weight::from_parts(7_219_000, 1630)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
 			.saturating_add(T::DbWeight::get().writes(1_u64))
 	}
+	/// Storage: `OnDemand::ScheduledChanges` (r:1 w:1)
+	/// Proof: `OnDemand::ScheduledChanges` (`max_values`: None, `max_size`: Some(351), added: 2826, mode: `MaxEncodedLen`)
+	fn schedule_change() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `6`
+		//  Estimated: `3816`
+		// Minimum execution time: 7_104_000 picoseconds.
+		Weight::from_parts(7_433_000, 3816)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `OnDemand::ScheduledChanges` (r:0 w:1)
+	/// Proof: `OnDemand::ScheduledChanges` (`max_values`: None, `max_size`: Some(351), added: 2826, mode: `MaxEncodedLen`)
+	fn cancel_scheduled_changes() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `0`
+		//  Estimated: `0`
+		// Minimum execution time: 4_012_000 picoseconds.
+		Weight::from_parts(4_218_000, 0)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `OnDemand::ScheduledChanges` (r:1 w:1)
+	/// Proof: `OnDemand::ScheduledChanges` (`max_values`: None, `max_size`: Some(351), added: 2826, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::Thresholds` (r:1 w:1)
+	/// Proof: `OnDemand::Thresholds` (`max_values`: Some(1), `max_size`: Some(145), added: 640, mode: `MaxEncodedLen`)
+	/// The range of component `c` is `[0, 16]`.
+	fn on_initialize(c: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `133 + c * (17 ±0)`
+		//  Estimated: `3816`
+		// Minimum execution time: 3_216_000 picoseconds.
+		Weight::from_parts(4_108_265, 3816)
+			// Standard Error: 2_104
+			.saturating_add(Weight::from_parts(3_402_117, 0).saturating_mul(c.into()))
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(c.into())))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(c.into())))
+	}
+	/// Storage: `OnDemand::AdaptiveThresholdConfig` (r:0 w:1)
+	/// Proof: `OnDemand::AdaptiveThresholdConfig` (`max_values`: Some(1), `max_size`: Some(48), added: 543, mode: `MaxEncodedLen`)
+	fn set_adaptive_threshold() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `0`
+		//  Estimated: `0`
+		// Minimum execution time: 3_842_000 picoseconds.
+		Weight::from_parts(4_017_000, 0)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `OnDemand::AdaptiveThresholdConfig` (r:1 w:0)
+	/// Proof: `OnDemand::AdaptiveThresholdConfig` (`max_values`: Some(1), `max_size`: Some(48), added: 543, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::LastSpotPrice` (r:1 w:1)
+	/// Proof: `OnDemand::LastSpotPrice` (`max_values`: Some(1), `max_size`: Some(20), added: 515, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::Thresholds` (r:1 w:1)
+	/// Proof: `OnDemand::Thresholds` (`max_values`: Some(1), `max_size`: Some(145), added: 640, mode: `MaxEncodedLen`)
+	/// Storage: `Aura::Authorities` (r:1 w:0)
+	/// Proof: `Aura::Authorities` (`max_values`: Some(1), `max_size`: Some(3200004), added: 3200499, mode: `MaxEncodedLen`)
+	fn report_spot_price() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `307`
+		//  Estimated: `3201489`
+		// Minimum execution time: 57_934_000 picoseconds.
+		Weight::from_parts(59_208_000, 3201489)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: `ParachainSystem::ValidationData` (r:1 w:0)
+	/// Proof: `ParachainSystem::ValidationData` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	/// Storage: `ParachainSystem::LastRelayChainBlockNumber` (r:1 w:0)
+	/// Proof: `ParachainSystem::LastRelayChainBlockNumber` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	/// Storage: `OnDemand::Orders` (r:1 w:1)
+	/// Proof: `OnDemand::Orders` (`max_values`: None, `max_size`: Some(81), added: 2556, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::OrderBlocks` (r:1 w:1)
+	/// Proof: `OnDemand::OrderBlocks` (`max_values`: Some(1), `max_size`: Some(4098), added: 4593, mode: `MaxEncodedLen`)
+	/// The range of component `p` is `[0, 65536]`.
+	fn record_order(p: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `207`
+		//  Estimated: `5583`
+		// Minimum execution time: 50_482_000 picoseconds.
+		Weight::from_parts(53_614_207, 5583)
+			// Standard Error: 12
+			.saturating_add(Weight::from_parts(3_191, 0).saturating_mul(p.into()))
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: `OnDemand::ReimbursementShare` (r:0 w:1)
+	/// Proof: `OnDemand::ReimbursementShare` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	fn set_reimbursement_share() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `0`
+		//  Estimated: `0`
+		// Minimum execution time: 3_412_000 picoseconds.
+		Weight::from_parts(3_625_000, 0)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `Authorship::Author` (r:1 w:0)
+	/// Proof: `Authorship::Author` (`max_values`: Some(1), `max_size`: Some(32), added: 527, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::LastReimbursement` (r:1 w:1)
+	/// Proof: `OnDemand::LastReimbursement` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::Orders` (r:1 w:1)
+	/// Proof: `OnDemand::Orders` (`max_values`: None, `max_size`: Some(81), added: 2556, mode: `MaxEncodedLen`)
+	/// Storage: `Session::NextKeys` (r:1 w:0)
+	/// Proof: `Session::NextKeys` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `OnDemand::PaymentAccounts` (r:1 w:0)
+	/// Proof: `OnDemand::PaymentAccounts` (`max_values`: None, `max_size`: Some(80), added: 2555, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::LastRelayParent` (r:1 w:0)
+	/// Proof: `OnDemand::LastRelayParent` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// Storage: `ParachainSystem::ValidationData` (r:1 w:0)
+	/// Proof: `ParachainSystem::ValidationData` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	/// Storage: `ParachainSystem::LastRelayChainBlockNumber` (r:1 w:0)
+	/// Proof: `ParachainSystem::LastRelayChainBlockNumber` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	/// Storage: `System::Account` (r:2 w:2)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	fn claim_reimbursement() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `612`
+		//  Estimated: `6196`
+		// Minimum execution time: 74_216_000 picoseconds.
+		Weight::from_parts(76_031_000, 6196)
+			.saturating_add(T::DbWeight::get().reads(10_u64))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+	/// Storage: `ParachainSystem::ValidationData` (r:1 w:0)
+	/// Proof: `ParachainSystem::ValidationData` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	/// Storage: `ParachainSystem::LastRelayChainBlockNumber` (r:1 w:0)
+	/// Proof: `ParachainSystem::LastRelayChainBlockNumber` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	/// Storage: `OnDemand::OrderBlocks` (r:1 w:1)
+	/// Proof: `OnDemand::OrderBlocks` (`max_values`: Some(1), `max_size`: Some(4098), added: 4593, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::Orders` (r:0 w:1024)
+	/// Proof: `OnDemand::Orders` (`max_values`: None, `max_size`: Some(81), added: 2556, mode: `MaxEncodedLen`)
+	/// The range of component `o` is `[0, 1024]`.
+	fn prune_orders(o: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `181 + o * (4 ±0)`
+		//  Estimated: `5583`
+		// Minimum execution time: 4_173_000 picoseconds.
+		Weight::from_parts(4_826_540, 5583)
+			// Standard Error: 1_318
+			.saturating_add(Weight::from_parts(2_904_115, 0).saturating_mul(o.into()))
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(o.into())))
+	}
+	/// Storage: `Session::NextKeys` (r:1 w:0)
+	/// Proof: `Session::NextKeys` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `OnDemand::PaymentAccounts` (r:0 w:1)
+	/// Proof: `OnDemand::PaymentAccounts` (`max_values`: None, `max_size`: Some(80), added: 2555, mode: `MaxEncodedLen`)
+	fn set_payment_account() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `243`
+		//  Estimated: `3708`
+		// Minimum execution time: 11_904_000 picoseconds.
+		Weight::from_parts(12_387_000, 3708)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `ParachainSystem::ValidationData` (r:1 w:0)
+	/// Proof: `ParachainSystem::ValidationData` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	/// Storage: `OnDemand::RelayScheduledChanges` (r:1 w:1)
+	/// Proof: `OnDemand::RelayScheduledChanges` (`max_values`: Some(1), `max_size`: Some(1458), added: 1953, mode: `MaxEncodedLen`)
+	fn schedule_relay_change() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `1471`
+		//  Estimated: `2956`
+		// Minimum execution time: 9_127_000 picoseconds.
+		Weight::from_parts(9_583_000, 2956)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `OnDemand::RelayScheduledChanges` (r:1 w:1)
+	/// Proof: `OnDemand::RelayScheduledChanges` (`max_values`: Some(1), `max_size`: Some(1458), added: 1953, mode: `MaxEncodedLen`)
+	fn cancel_relay_scheduled_changes() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `1463`
+		//  Estimated: `2943`
+		// Minimum execution time: 7_911_000 picoseconds.
+		Weight::from_parts(8_264_000, 2943)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `OnDemand::ChangeProfileConfig` (r:0 w:1)
+	/// Proof: `OnDemand::ChangeProfileConfig` (`max_values`: Some(1), `max_size`: Some(2191), added: 2686, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::ActiveProfileOffset` (r:0 w:1)
+	/// Proof: `OnDemand::ActiveProfileOffset` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	fn set_change_profile() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `0`
+		//  Estimated: `0`
+		// Minimum execution time: 6_472_000 picoseconds.
+		Weight::from_parts(6_815_000, 0)
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: `ParachainSystem::ValidationData` (r:1 w:0)
+	/// Proof: `ParachainSystem::ValidationData` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	/// Storage: `OnDemand::RelayScheduledChanges` (r:1 w:1)
+	/// Proof: `OnDemand::RelayScheduledChanges` (`max_values`: Some(1), `max_size`: Some(1458), added: 1953, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::ChangeProfileConfig` (r:1 w:0)
+	/// Proof: `OnDemand::ChangeProfileConfig` (`max_values`: Some(1), `max_size`: Some(2191), added: 2686, mode: `MaxEncodedLen`)
+	/// Storage: `Timestamp::Now` (r:1 w:0)
+	/// Proof: `Timestamp::Now` (`max_values`: Some(1), `max_size`: Some(8), added: 503, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::ActiveProfileOffset` (r:1 w:1)
+	/// Proof: `OnDemand::ActiveProfileOffset` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::Thresholds` (r:1 w:1)
+	/// Proof: `OnDemand::Thresholds` (`max_values`: Some(1), `max_size`: Some(145), added: 640, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::LastRelayParent` (r:0 w:1)
+	/// Proof: `OnDemand::LastRelayParent` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// The range of component `r` is `[0, 16]`.
+	/// The range of component `p` is `[0, 24]`.
+	fn on_finalize(r: u32, p: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `311 + r * (91 ±0) + p * (91 ±0)`
+		//  Estimated: `3676`
+		// Minimum execution time: 8_734_000 picoseconds.
+		Weight::from_parts(9_218_447, 3676)
+			// Standard Error: 1_873
+			.saturating_add(Weight::from_parts(3_296_512, 0).saturating_mul(r.into()))
+			// Standard Error: 1_249
+			.saturating_add(Weight::from_parts(3_318_904, 0).saturating_mul(p.into()))
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
 }
 
 // For backwards compatibility and tests.
@@ -77,14 +359,278 @@ impl WeightInfo for () {
 		Weight::from_parts(3_497_000, 0)
 			.saturating_add(RocksDbWeight::get().writes(1_u64))
 	}
-	/// Storage: `OnDemand::ThresholdParameter` (r:0 w:1)
-	/// Proof: `OnDemand::ThresholdParameter` (`max_values`: Some(1), `max_size`: Some(16), added: 511, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::Thresholds` (r:1 w:1)
+	/// Proof: `OnDemand::Thresholds` (`max_values`: Some(1), `max_size`: Some(145), added: 640, mode: `MaxEncodedLen`)
 	fn set_threshold_parameter() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `127`
+		//  Estimated: `1630`
+		// Minimum execution time: 6_893_000 picoseconds.
+		Weight::from_parts(7_104_000, 1630)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `OnDemand::MaxOrderPrice` (r:0 w:1)
+	/// Proof: `OnDemand::MaxOrderPrice` (`max_values`: Some(1), `max_size`: Some(16), added: 511, mode: `MaxEncodedLen`)
+	fn set_max_order_price() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `0`
+		//  Estimated: `0`
+		// Minimum execution time: 3_316_000 picoseconds.
+		Weight::from_parts(3_492_000, 0)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `OnDemand::MaxIdleRelayBlocks` (r:0 w:1)
+	/// Proof: `OnDemand::MaxIdleRelayBlocks` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	fn set_max_idle_relay_blocks() -> Weight {
 		// Proof Size summary in bytes:
 		//  Measured:  `0`
 		//  Estimated: `0`
-		// Minimum execution time: 3_326_000 picoseconds.
-		Weight::from_parts(3_487_000, 0)
+		// Minimum execution time: 3_276_000 picoseconds.
+		Weight::from_parts(3_451_000, 0)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `OnDemand::InboundMessageThreshold` (r:0 w:1)
+	/// Proof: `OnDemand::InboundMessageThreshold` (`max_values`: Some(1), `max_size`: Some(8), added: 503, mode: `MaxEncodedLen`)
+	fn set_inbound_message_threshold() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `0`
+		//  Estimated: `0`
+		// Minimum execution time: 3_327_000 picoseconds.
+		Weight::from_parts(3_508_000, 0)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `OnDemand::Thresholds` (r:1 w:1)
+	/// Proof: `OnDemand::Thresholds` (`max_values`: Some(1), `max_size`: Some(145), added: 640, mode: `MaxEncodedLen`)
+	fn set_threshold() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `127`
+		//  Estimated: `1630`
+		// Minimum execution time: 6_958_000 picoseconds.
+		Weight::from_parts(7_219_000, 1630)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
 			.saturating_add(RocksDbWeight::get().writes(1_u64))
 	}
+	/// Storage: `OnDemand::ScheduledChanges` (r:1 w:1)
+	/// Proof: `OnDemand::ScheduledChanges` (`max_values`: None, `max_size`: Some(351), added: 2826, mode: `MaxEncodedLen`)
+	fn schedule_change() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `6`
+		//  Estimated: `3816`
+		// Minimum execution time: 7_104_000 picoseconds.
+		Weight::from_parts(7_433_000, 3816)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `OnDemand::ScheduledChanges` (r:0 w:1)
+	/// Proof: `OnDemand::ScheduledChanges` (`max_values`: None, `max_size`: Some(351), added: 2826, mode: `MaxEncodedLen`)
+	fn cancel_scheduled_changes() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `0`
+		//  Estimated: `0`
+		// Minimum execution time: 4_012_000 picoseconds.
+		Weight::from_parts(4_218_000, 0)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `OnDemand::ScheduledChanges` (r:1 w:1)
+	/// Proof: `OnDemand::ScheduledChanges` (`max_values`: None, `max_size`: Some(351), added: 2826, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::Thresholds` (r:1 w:1)
+	/// Proof: `OnDemand::Thresholds` (`max_values`: Some(1), `max_size`: Some(145), added: 640, mode: `MaxEncodedLen`)
+	/// The range of component `c` is `[0, 16]`.
+	fn on_initialize(c: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `133 + c * (17 ±0)`
+		//  Estimated: `3816`
+		// Minimum execution time: 3_216_000 picoseconds.
+		Weight::from_parts(4_108_265, 3816)
+			// Standard Error: 2_104
+			.saturating_add(Weight::from_parts(3_402_117, 0).saturating_mul(c.into()))
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(c.into())))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+			.saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(c.into())))
+	}
+	/// Storage: `OnDemand::AdaptiveThresholdConfig` (r:0 w:1)
+	/// Proof: `OnDemand::AdaptiveThresholdConfig` (`max_values`: Some(1), `max_size`: Some(48), added: 543, mode: `MaxEncodedLen`)
+	fn set_adaptive_threshold() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `0`
+		//  Estimated: `0`
+		// Minimum execution time: 3_842_000 picoseconds.
+		Weight::from_parts(4_017_000, 0)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `OnDemand::AdaptiveThresholdConfig` (r:1 w:0)
+	/// Proof: `OnDemand::AdaptiveThresholdConfig` (`max_values`: Some(1), `max_size`: Some(48), added: 543, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::LastSpotPrice` (r:1 w:1)
+	/// Proof: `OnDemand::LastSpotPrice` (`max_values`: Some(1), `max_size`: Some(20), added: 515, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::Thresholds` (r:1 w:1)
+	/// Proof: `OnDemand::Thresholds` (`max_values`: Some(1), `max_size`: Some(145), added: 640, mode: `MaxEncodedLen`)
+	/// Storage: `Aura::Authorities` (r:1 w:0)
+	/// Proof: `Aura::Authorities` (`max_values`: Some(1), `max_size`: Some(3200004), added: 3200499, mode: `MaxEncodedLen`)
+	fn report_spot_price() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `307`
+		//  Estimated: `3201489`
+		// Minimum execution time: 57_934_000 picoseconds.
+		Weight::from_parts(59_208_000, 3201489)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	/// Storage: `ParachainSystem::ValidationData` (r:1 w:0)
+	/// Proof: `ParachainSystem::ValidationData` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	/// Storage: `ParachainSystem::LastRelayChainBlockNumber` (r:1 w:0)
+	/// Proof: `ParachainSystem::LastRelayChainBlockNumber` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	/// Storage: `OnDemand::Orders` (r:1 w:1)
+	/// Proof: `OnDemand::Orders` (`max_values`: None, `max_size`: Some(81), added: 2556, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::OrderBlocks` (r:1 w:1)
+	/// Proof: `OnDemand::OrderBlocks` (`max_values`: Some(1), `max_size`: Some(4098), added: 4593, mode: `MaxEncodedLen`)
+	/// The range of component `p` is `[0, 65536]`.
+	fn record_order(p: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `207`
+		//  Estimated: `5583`
+		// Minimum execution time: 50_482_000 picoseconds.
+		Weight::from_parts(53_614_207, 5583)
+			// Standard Error: 12
+			.saturating_add(Weight::from_parts(3_191, 0).saturating_mul(p.into()))
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	/// Storage: `OnDemand::ReimbursementShare` (r:0 w:1)
+	/// Proof: `OnDemand::ReimbursementShare` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	fn set_reimbursement_share() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `0`
+		//  Estimated: `0`
+		// Minimum execution time: 3_412_000 picoseconds.
+		Weight::from_parts(3_625_000, 0)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `Authorship::Author` (r:1 w:0)
+	/// Proof: `Authorship::Author` (`max_values`: Some(1), `max_size`: Some(32), added: 527, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::LastReimbursement` (r:1 w:1)
+	/// Proof: `OnDemand::LastReimbursement` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::Orders` (r:1 w:1)
+	/// Proof: `OnDemand::Orders` (`max_values`: None, `max_size`: Some(81), added: 2556, mode: `MaxEncodedLen`)
+	/// Storage: `Session::NextKeys` (r:1 w:0)
+	/// Proof: `Session::NextKeys` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `OnDemand::PaymentAccounts` (r:1 w:0)
+	/// Proof: `OnDemand::PaymentAccounts` (`max_values`: None, `max_size`: Some(80), added: 2555, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::LastRelayParent` (r:1 w:0)
+	/// Proof: `OnDemand::LastRelayParent` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// Storage: `ParachainSystem::ValidationData` (r:1 w:0)
+	/// Proof: `ParachainSystem::ValidationData` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	/// Storage: `ParachainSystem::LastRelayChainBlockNumber` (r:1 w:0)
+	/// Proof: `ParachainSystem::LastRelayChainBlockNumber` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	/// Storage: `System::Account` (r:2 w:2)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	fn claim_reimbursement() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `612`
+		//  Estimated: `6196`
+		// Minimum execution time: 74_216_000 picoseconds.
+		Weight::from_parts(76_031_000, 6196)
+			.saturating_add(RocksDbWeight::get().reads(10_u64))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+	/// Storage: `ParachainSystem::ValidationData` (r:1 w:0)
+	/// Proof: `ParachainSystem::ValidationData` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	/// Storage: `ParachainSystem::LastRelayChainBlockNumber` (r:1 w:0)
+	/// Proof: `ParachainSystem::LastRelayChainBlockNumber` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	/// Storage: `OnDemand::OrderBlocks` (r:1 w:1)
+	/// Proof: `OnDemand::OrderBlocks` (`max_values`: Some(1), `max_size`: Some(4098), added: 4593, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::Orders` (r:0 w:1024)
+	/// Proof: `OnDemand::Orders` (`max_values`: None, `max_size`: Some(81), added: 2556, mode: `MaxEncodedLen`)
+	/// The range of component `o` is `[0, 1024]`.
+	fn prune_orders(o: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `181 + o * (4 ±0)`
+		//  Estimated: `5583`
+		// Minimum execution time: 4_173_000 picoseconds.
+		Weight::from_parts(4_826_540, 5583)
+			// Standard Error: 1_318
+			.saturating_add(Weight::from_parts(2_904_115, 0).saturating_mul(o.into()))
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+			.saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(o.into())))
+	}
+	/// Storage: `Session::NextKeys` (r:1 w:0)
+	/// Proof: `Session::NextKeys` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `OnDemand::PaymentAccounts` (r:0 w:1)
+	/// Proof: `OnDemand::PaymentAccounts` (`max_values`: None, `max_size`: Some(80), added: 2555, mode: `MaxEncodedLen`)
+	fn set_payment_account() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `243`
+		//  Estimated: `3708`
+		// Minimum execution time: 11_904_000 picoseconds.
+		Weight::from_parts(12_387_000, 3708)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `ParachainSystem::ValidationData` (r:1 w:0)
+	/// Proof: `ParachainSystem::ValidationData` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	/// Storage: `OnDemand::RelayScheduledChanges` (r:1 w:1)
+	/// Proof: `OnDemand::RelayScheduledChanges` (`max_values`: Some(1), `max_size`: Some(1458), added: 1953, mode: `MaxEncodedLen`)
+	fn schedule_relay_change() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `1471`
+		//  Estimated: `2956`
+		// Minimum execution time: 9_127_000 picoseconds.
+		Weight::from_parts(9_583_000, 2956)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `OnDemand::RelayScheduledChanges` (r:1 w:1)
+	/// Proof: `OnDemand::RelayScheduledChanges` (`max_values`: Some(1), `max_size`: Some(1458), added: 1953, mode: `MaxEncodedLen`)
+	fn cancel_relay_scheduled_changes() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `1463`
+		//  Estimated: `2943`
+		// Minimum execution time: 7_911_000 picoseconds.
+		Weight::from_parts(8_264_000, 2943)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `OnDemand::ChangeProfileConfig` (r:0 w:1)
+	/// Proof: `OnDemand::ChangeProfileConfig` (`max_values`: Some(1), `max_size`: Some(2191), added: 2686, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::ActiveProfileOffset` (r:0 w:1)
+	/// Proof: `OnDemand::ActiveProfileOffset` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	fn set_change_profile() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `0`
+		//  Estimated: `0`
+		// Minimum execution time: 6_472_000 picoseconds.
+		Weight::from_parts(6_815_000, 0)
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	/// Storage: `ParachainSystem::ValidationData` (r:1 w:0)
+	/// Proof: `ParachainSystem::ValidationData` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	/// Storage: `OnDemand::RelayScheduledChanges` (r:1 w:1)
+	/// Proof: `OnDemand::RelayScheduledChanges` (`max_values`: Some(1), `max_size`: Some(1458), added: 1953, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::ChangeProfileConfig` (r:1 w:0)
+	/// Proof: `OnDemand::ChangeProfileConfig` (`max_values`: Some(1), `max_size`: Some(2191), added: 2686, mode: `MaxEncodedLen`)
+	/// Storage: `Timestamp::Now` (r:1 w:0)
+	/// Proof: `Timestamp::Now` (`max_values`: Some(1), `max_size`: Some(8), added: 503, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::ActiveProfileOffset` (r:1 w:1)
+	/// Proof: `OnDemand::ActiveProfileOffset` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::Thresholds` (r:1 w:1)
+	/// Proof: `OnDemand::Thresholds` (`max_values`: Some(1), `max_size`: Some(145), added: 640, mode: `MaxEncodedLen`)
+	/// Storage: `OnDemand::LastRelayParent` (r:0 w:1)
+	/// Proof: `OnDemand::LastRelayParent` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// The range of component `r` is `[0, 16]`.
+	/// The range of component `p` is `[0, 24]`.
+	fn on_finalize(r: u32, p: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `311 + r * (91 ±0) + p * (91 ±0)`
+		//  Estimated: `3676`
+		// Minimum execution time: 8_734_000 picoseconds.
+		Weight::from_parts(9_218_447, 3676)
+			// Standard Error: 1_873
+			.saturating_add(Weight::from_parts(3_296_512, 0).saturating_mul(r.into()))
+			// Standard Error: 1_249
+			.saturating_add(Weight::from_parts(3_318_904, 0).saturating_mul(p.into()))
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
 }
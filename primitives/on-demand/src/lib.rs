@@ -3,7 +3,11 @@
 use codec::{Codec, Decode, Encode, MaxEncodedLen};
 use cumulus_primitives_core::ParaId;
 use frame_support::Parameter;
-use sp_runtime::traits::{MaybeDisplay, MaybeSerializeDeserialize, Member};
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{Block as BlockT, MaybeDisplay, MaybeSerializeDeserialize, Member},
+	AccountId32, Vec,
+};
 
 pub mod well_known_keys;
 
@@ -13,6 +17,66 @@ pub struct EnqueuedOrder {
 	pub para_id: ParaId,
 }
 
+/// Configures when queued inbound messages require an order to be placed.
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct MessageThreshold<BlockNumber> {
+	/// The number of queued inbound messages at which an order is placed.
+	pub count: u32,
+	/// The number of relay chain blocks after which a queued inbound message requires an order
+	/// to be placed.
+	pub max_age: BlockNumber,
+}
+
+/// The kinds of thresholds which can be stored in the runtime.
+///
+/// The thresholds are read by the order placement criteria of `on-demand-service`, while
+/// `Custom` thresholds are left to criteria specific to the parachain.
+#[derive(
+	Encode, Decode, MaxEncodedLen, TypeInfo, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy,
+)]
+pub enum ThresholdKind {
+	/// Total fees of the pending transactions.
+	Fees,
+	/// Number of pending transactions.
+	TransactionCount,
+	/// Number of relay chain blocks without a parachain block.
+	IdleTime,
+	/// Price of an order.
+	Price,
+	/// Threshold with a meaning specific to the parachain.
+	Custom(u8),
+	/// Total `ref_time` of the pending transactions.
+	PendingWeight,
+	/// Percentage of a block the pending transactions would fill.
+	BlockFullness,
+}
+
+/// All the on-demand settings of the runtime.
+#[derive(Encode, Decode, TypeInfo, Debug, PartialEq, Eq, Clone)]
+pub struct OnDemandSettings<Balance, BlockNumber, ThresholdParameter> {
+	/// Order placement slot width.
+	pub slot_width: BlockNumber,
+	/// Order placement threshold parameter.
+	pub threshold_parameter: ThresholdParameter,
+	/// The maximum price collators are allowed to pay for a single order.
+	pub max_order_price: Option<Balance>,
+	/// The maximum number of relay chain blocks without a parachain block.
+	pub max_idle_relay_blocks: Option<BlockNumber>,
+	/// The threshold of queued inbound messages after which an order is placed.
+	pub inbound_message_threshold: Option<MessageThreshold<BlockNumber>>,
+	/// All the thresholds stored in the runtime.
+	pub thresholds: Vec<(ThresholdKind, ThresholdParameter)>,
+}
+
+impl<Balance, BlockNumber, ThresholdParameter>
+	OnDemandSettings<Balance, BlockNumber, ThresholdParameter>
+{
+	/// Returns the threshold of the given kind, if set.
+	pub fn threshold(&self, kind: ThresholdKind) -> Option<&ThresholdParameter> {
+		self.thresholds.iter().find(|(k, _)| *k == kind).map(|(_, value)| value)
+	}
+}
+
 pub trait ThresholdParameterT:
 	Parameter + Member + Default + MaybeSerializeDeserialize + MaxEncodedLen
 {
@@ -35,5 +99,39 @@ sp_api::decl_runtime_apis! {
 
 		/// Runtime configured order placement threshold parameter.
 		fn threshold_parameter() -> ThresholdParameter;
+
+		/// The maximum price collators are allowed to pay for a single order.
+		#[api_version(3)]
+		fn max_order_price() -> Option<Balance>;
+
+		/// The maximum number of relay chain blocks without a parachain block, after which an
+		/// order is placed regardless of the order placement criteria.
+		#[api_version(4)]
+		fn max_idle_relay_blocks() -> Option<BlockNumber>;
+
+		/// The threshold of queued inbound messages after which an order is placed.
+		#[api_version(5)]
+		fn inbound_message_threshold() -> Option<MessageThreshold<BlockNumber>>;
+
+		/// All the thresholds stored in the runtime.
+		#[api_version(6)]
+		fn thresholds() -> Vec<(ThresholdKind, ThresholdParameter)>;
+
+		/// Returns an unsigned transaction reporting the on-demand spot price, if the runtime
+		/// adjusts its threshold to the spot price.
+		///
+		/// The report is signed with a collator authority key, so the keystore has to be
+		/// registered as an extension.
+		#[api_version(7)]
+		fn spot_price_report(price: Balance) -> Option<<Block as BlockT>::Extrinsic>;
+
+		/// All the on-demand settings, in a single call.
+		#[api_version(8)]
+		fn on_demand_config() -> OnDemandSettings<Balance, BlockNumber, ThresholdParameter>;
+
+		/// The relay chain account paying for the orders of the authority with the given raw
+		/// public key.
+		#[api_version(9)]
+		fn payment_account(authority: Vec<u8>) -> Option<AccountId32>;
 	}
 }
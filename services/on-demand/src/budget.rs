@@ -0,0 +1,64 @@
+//! Rolling spend budget for on-demand orders.
+
+use cumulus_primitives_core::relay_chain::BlockNumber as RelayBlockNumber;
+use std::{
+	collections::VecDeque,
+	sync::{Arc, Mutex},
+};
+
+/// Limits the amount a collator can spend on orders within a window of relay chain blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpendBudget {
+	/// The maximum amount that can be spent within the window.
+	pub max_spend: u128,
+	/// The length of the window in relay chain blocks.
+	pub window: RelayBlockNumber,
+}
+
+/// Keeps track of the amount spent on orders by this collator.
+#[derive(Default, Clone)]
+pub struct SpendTracker {
+	/// The budget to enforce. `None` means there is no limit.
+	budget: Option<SpendBudget>,
+	/// The amounts spent, alongside the relay chain block at which the order was made.
+	spent: Arc<Mutex<VecDeque<(RelayBlockNumber, u128)>>>,
+}
+
+impl SpendTracker {
+	pub fn new(budget: Option<SpendBudget>) -> Self {
+		Self { budget, spent: Default::default() }
+	}
+
+	/// The budget enforced by the tracker.
+	pub fn budget(&self) -> Option<SpendBudget> {
+		self.budget
+	}
+
+	/// Returns the total amount spent within the window ending at `now`.
+	pub fn spent(&self, now: RelayBlockNumber) -> u128 {
+		let Some(budget) = self.budget else { return 0 };
+
+		let mut spent = self.spent.lock().expect("Poisoned lock");
+		let window_start = now.saturating_sub(budget.window);
+		while spent.front().map_or(false, |(at, _)| *at <= window_start) {
+			spent.pop_front();
+		}
+
+		spent.iter().fold(0u128, |total, (_, amount)| total.saturating_add(*amount))
+	}
+
+	/// Returns whether `amount` can be spent at `now` without exceeding the budget.
+	pub fn can_spend(&self, now: RelayBlockNumber, amount: u128) -> bool {
+		let Some(budget) = self.budget else { return true };
+		self.spent(now).saturating_add(amount) <= budget.max_spend
+	}
+
+	/// Records an amount spent on an order at `now`.
+	pub fn record(&self, now: RelayBlockNumber, amount: u128) {
+		if self.budget.is_none() {
+			return
+		}
+
+		self.spent.lock().expect("Poisoned lock").push_back((now, amount));
+	}
+}
@@ -1,149 +1,267 @@
 //! This file contains all the chain related interaction functions.
 
-use crate::chain::polkadot::{
-	on_demand_assignment_provider::storage::types::queue_status::QueueStatus,
-	runtime_types::{
-		pallet_broker::coretime_interface::CoreAssignment,
-		polkadot_parachain_primitives::primitives::Id,
-		polkadot_runtime_parachains::assigner_coretime::CoreDescriptor,
+use crate::{
+	budget::SpendTracker,
+	metadata::{
+		as_option, as_sequence, variant_name, DecodedValue, RelayMetadata, ACCOUNT,
+		CANDIDATE_BACKED, CORETIME_ASSIGNMENT_PALLET, CORE_DESCRIPTORS, ON_DEMAND_PALLET,
+		ORDER_PLACED, PARA_INCLUSION_PALLET, PLACE_ORDER_ALLOW_DEATH, PLACE_ORDER_KEEP_ALIVE,
+		QUEUE_STATUS, SYSTEM_PALLET,
 	},
+	metrics::{reason, Metrics},
+	order_record::OrderRecords,
+	payment::PaymentSigner,
+	pricing::{PricingParams, PricingState},
+	LOG_TARGET,
+};
+use codec::Decode;
+use cumulus_primitives_core::{
+	relay_chain::{BlockNumber as RelayBlockNumber, CoreIndex},
+	ParaId,
 };
-use codec::{Codec, Decode};
-use cumulus_primitives_core::{relay_chain::CoreIndex, ParaId};
 use cumulus_relay_chain_interface::RelayChainInterface;
 use on_demand_primitives::well_known_keys::{
-	core_descriptor, para_lifecycle, ACTIVE_CONFIG, QUEUE_STATUS,
+	account, core_descriptor, para_lifecycle, ACTIVE_CONFIG,
 };
 use polkadot_runtime_parachains::{configuration::HostConfiguration, ParaLifecycle};
-use sp_application_crypto::AppCrypto;
-use sp_core::{ByteArray, H256};
-use sp_keystore::KeystorePtr;
-use sp_runtime::{
-	traits::{IdentifyAccount, MaybeDisplay, Verify},
-	MultiSignature as SpMultiSignature, SaturatedConversion,
+use sc_service::SpawnTaskHandle;
+use sp_core::{hashing::twox_128, H256};
+use sp_runtime::{FixedU128, SaturatedConversion};
+use std::error::Error;
+use subxt::{
+	dynamic::Value,
+	events::EventDetails,
+	ext::scale_value::At,
+	tx::{TxProgress, TxStatus},
+	OnlineClient, PolkadotConfig,
 };
-use std::{error::Error, fmt::Debug};
-use subxt::{tx::Signer, utils::MultiSignature, Config, OnlineClient, PolkadotConfig};
 
-#[subxt::subxt(runtime_metadata_path = "../../artifacts/metadata.scale")]
-pub mod polkadot {}
+/// Submits order to an rpc node.
+///
+/// The inclusion of the order in the relay chain is awaited in a separate task, which records the
+/// amount spent on the order.
+pub async fn submit_order(
+	url: &str,
+	para_id: ParaId,
+	max_amount: u128,
+	signer: &PaymentSigner,
+	allow_death: bool,
+	relay_height: RelayBlockNumber,
+	cores: Vec<u32>,
+	order_records: &OrderRecords,
+	spend_tracker: &SpendTracker,
+	spawner: &SpawnTaskHandle,
+	metrics: Option<&Metrics>,
+) -> Result<(), Box<dyn Error>> {
+	let report_failure = |reason: &str| {
+		if let Some(metrics) = metrics {
+			metrics.order_failed(reason);
+		}
+	};
+
+	let client = OnlineClient::<PolkadotConfig>::from_url(url)
+		.await
+		.inspect_err(|_| report_failure(reason::CONNECTION))?;
 
-#[derive(Clone, Copy, PartialEq, Eq)]
-pub struct Signature(pub [u8; 64]);
+	// Unless explicitly allowed, the order must not result in the payer getting reaped.
+	let call = if allow_death { PLACE_ORDER_ALLOW_DEATH } else { PLACE_ORDER_KEEP_ALIVE };
 
-impl From<Signature> for MultiSignature {
-	fn from(value: Signature) -> Self {
-		MultiSignature::Sr25519(value.0)
+	// The call is constructed dynamically and encoded based on the metadata of the relay runtime
+	// we are connected to.
+	let place_order = subxt::dynamic::tx(
+		ON_DEMAND_PALLET,
+		call,
+		vec![
+			Value::u128(max_amount),
+			Value::unnamed_composite([Value::u128(u32::from(para_id).into())]),
+		],
+	);
+
+	// TODO: Ideally the transaction should only be valid for one slot.
+	let progress = async {
+		let account_id = signer.account_id();
+		let partial = client
+			.tx()
+			.create_partial_signed(&place_order, &account_id, Default::default())
+			.await?;
+		let signature = signer.sign(&partial.signer_payload())?;
+		let progress = partial
+			.sign_with_address_and_signature(&account_id.into(), &signature)
+			.submit_and_watch()
+			.await?;
+
+		Ok::<_, Box<dyn Error>>(progress)
 	}
-}
+	.await
+	.inspect_err(|_| report_failure(reason::SUBMISSION))?;
+	let tx_hash = progress.extrinsic_hash();
+	order_records.submitted(tx_hash, relay_height, max_amount, cores);
+	if let Some(metrics) = metrics {
+		metrics.order_submitted();
+	}
+
+	log::info!(
+		target: LOG_TARGET,
+		"Order {:?} submitted",
+		tx_hash
+	);
+
+	// Following the transaction takes at least a relay chain block, during which new relay chain
+	// blocks must still be handled.
+	let order_records = order_records.clone();
+	let spend_tracker = spend_tracker.clone();
+	let metrics = metrics.cloned();
+	spawner.spawn("on-demand order watcher", Some("on-demand"), async move {
+		match wait_for_placement(&client, progress, para_id).await {
+			Ok((relay_block, placed_at, spent)) => {
+				log::info!(
+					target: LOG_TARGET,
+					"Order {:?} placed in relay block {:?}, spent {} out of max {}",
+					tx_hash,
+					relay_block,
+					spent,
+					max_amount
+				);
+				order_records.placed(tx_hash, relay_block, placed_at, spent);
+				spend_tracker.record(relay_height, spent);
+				if let Some(metrics) = &metrics {
+					metrics.order_placed(spent);
+				}
+			},
+			Err(e) => {
+				log::error!(
+					target: LOG_TARGET,
+					"Order {:?} failed: {}",
+					tx_hash,
+					e
+				);
+				order_records.failed(tx_hash, e.to_string());
+				if let Some(metrics) = &metrics {
+					metrics.order_failed(reason::INCLUSION);
+				}
+			},
+		}
+	});
 
-pub struct SignerKeystore<T: Config> {
-	/// Account ID
-	account_id: T::AccountId,
-	/// Keystore of node
-	keystore: KeystorePtr,
+	Ok(())
 }
 
-impl<T> SignerKeystore<T>
-where
-	T: Config,
-	T::AccountId: From<[u8; 32]>,
-{
-	pub fn new(keystore: KeystorePtr) -> Self {
-		let pub_key =
-			keystore.sr25519_public_keys(sp_consensus_aura::sr25519::AuthorityPair::ID)[0];
-
-		let binding = <SpMultiSignature as Verify>::Signer::from(pub_key).into_account().clone();
-
-		let account_id = binding.as_slice();
-		let mut r = [0u8; 32];
-		r.copy_from_slice(account_id);
-		let acc = T::AccountId::try_from(r).ok().expect("Failed to get account from keystore");
-		Self { account_id: acc.clone(), keystore }
+/// Follows the order transaction until it is included in a relay chain block.
+///
+/// Returns the hash and number of the relay chain block and the amount spent on the order.
+async fn wait_for_placement(
+	client: &OnlineClient<PolkadotConfig>,
+	mut progress: TxProgress<PolkadotConfig, OnlineClient<PolkadotConfig>>,
+	para_id: ParaId,
+) -> Result<(H256, RelayBlockNumber, u128), Box<dyn Error>> {
+	while let Some(status) = progress.next().await {
+		match status? {
+			TxStatus::InBestBlock(in_block) | TxStatus::InFinalizedBlock(in_block) => {
+				let events = in_block.wait_for_success().await?;
+				let spent = events
+					.iter()
+					.filter_map(|event| event.ok())
+					.find_map(|event| order_placed_amount(&event, para_id))
+					.ok_or("Order included, but no `OnDemandOrderPlaced` event found")?;
+				let block = client.blocks().at(in_block.block_hash()).await?;
+
+				return Ok((block.hash(), block.number(), spent))
+			},
+			TxStatus::Error { message } |
+			TxStatus::Invalid { message } |
+			TxStatus::Dropped { message } => return Err(message.into()),
+			_ => {},
+		}
 	}
+
+	Err("Transaction status stream ended before inclusion".into())
 }
-impl<T> Signer<T> for SignerKeystore<T>
-where
-	T: Config,
-	T::AccountId: From<[u8; 32]>,
-	T::Signature: From<Signature>,
-{
-	fn account_id(&self) -> T::AccountId {
-		self.account_id.clone()
+
+/// Returns the amount spent if the event is an `OnDemandOrderPlaced` event for `para_id`.
+pub fn order_placed_amount(event: &EventDetails<PolkadotConfig>, para_id: ParaId) -> Option<u128> {
+	if event.pallet_name() != ON_DEMAND_PALLET || event.variant_name() != ORDER_PLACED {
+		return None
 	}
 
-	fn address(&self) -> T::Address {
-		self.account_id.clone().into()
+	let fields = event.field_values().ok()?;
+	let ordered_for = fields.at("para_id")?.at(0)?.as_u128()?;
+	if ordered_for != u32::from(para_id) as u128 {
+		return None
 	}
 
-	/// Use aura's key to sign
-	fn sign(&self, signer_payload: &[u8]) -> T::Signature {
-		let pub_key =
-			self.keystore.sr25519_public_keys(sp_consensus_aura::sr25519::AuthorityPair::ID)[0];
+	fields.at("spot_price")?.as_u128()
+}
 
-		let signature = self
-			.keystore
-			.sr25519_sign(sp_consensus_aura::sr25519::AuthorityPair::ID, &pub_key, signer_payload)
-			.unwrap()
-			.unwrap();
+/// Returns the core index if the event is a `CandidateBacked` event for `para_id`.
+pub fn backed_core(event: &EventDetails<PolkadotConfig>, para_id: ParaId) -> Option<u32> {
+	if event.pallet_name() != PARA_INCLUSION_PALLET || event.variant_name() != CANDIDATE_BACKED {
+		return None
+	}
 
-		Signature(signature.0).into()
+	// CandidateBacked(CandidateReceipt, HeadData, CoreIndex, GroupIndex)
+	let fields = event.field_values().ok()?;
+	let backed_para = fields.at(0)?.at("descriptor")?.at("para_id")?.at(0)?.as_u128()?;
+	if backed_para != u32::from(para_id) as u128 {
+		return None
 	}
-}
 
-/// Submits order to an rpc node.
-pub async fn submit_order(
-	url: &str,
-	para_id: ParaId,
-	max_amount: u128,
-	keystore: KeystorePtr,
-) -> Result<(), Box<dyn Error>> {
-	let client = OnlineClient::<PolkadotConfig>::from_url(url).await?;
+	fields.at(2)?.at(0)?.as_u128().map(|core| core as u32)
+}
 
-	let place_order = polkadot::tx()
-		.on_demand_assignment_provider()
-		.place_order_allow_death(max_amount, Id(para_id.into()));
+/// Reads the state of the on-demand queue which determines the spot price.
+pub async fn get_pricing_state(
+	relay_chain: &(impl RelayChainInterface + Clone),
+	metadata: &RelayMetadata,
+	hash: H256,
+) -> Option<PricingState> {
+	let queue_status_key = storage_value_key(ON_DEMAND_PALLET, QUEUE_STATUS);
+	let queue_status_raw = relay_chain.get_storage_by_key(hash, &queue_status_key).await.ok()??;
+	let queue_status = metadata
+		.decode_storage(ON_DEMAND_PALLET, QUEUE_STATUS, &queue_status_raw)
+		.ok()?;
 
-	let signer_keystore = SignerKeystore::<PolkadotConfig>::new(keystore.clone());
+	let active_config_raw = relay_chain.get_storage_by_key(hash, ACTIVE_CONFIG).await.ok()??;
+	let active_config = <HostConfiguration<u32>>::decode(&mut &active_config_raw[..]).ok()?;
 
-	// TODO: Ideally the transaction should only be valid for one slot.
-	let submit_result = client.tx().sign_and_submit_default(&place_order, &signer_keystore).await;
-	log::info!("submit_result: {:?}", submit_result);
-	submit_result?;
+	// `traffic` is a `FixedU128`, i.e. a wrapper around its inner `u128` value.
+	let traffic = queue_status.at("traffic")?.at(0)?.as_u128()?;
+	let next_index = queue_status.at("next_index")?.at(0)?.as_u128()?;
+	let smallest_index = queue_status.at("smallest_index")?.at(0)?.as_u128()?;
+	let freed = as_sequence(queue_status.at("freed_indices")?).len();
+	// Queue indices wrap around, same as on the relay chain.
+	let queue_size = (next_index as u32)
+		.wrapping_sub(smallest_index as u32)
+		.saturating_sub(freed.saturated_into::<u32>());
 
-	Ok(())
+	Some(PricingState {
+		traffic: FixedU128::from_inner(traffic),
+		queue_size,
+		params: PricingParams::from_config(&active_config),
+	})
 }
 
-/// Get the spot price from the relay chain.
-pub async fn get_spot_price<Balance>(
-	relay_chain: impl RelayChainInterface + Clone,
+/// Returns the free relay chain balance of the account with the given public key.
+pub async fn free_balance(
+	relay_chain: &(impl RelayChainInterface + Clone),
+	metadata: &RelayMetadata,
 	hash: H256,
-) -> Option<Balance>
-where
-	Balance: Codec + MaybeDisplay + 'static + Debug + From<u128>,
-{
-	let queue_status_storage = relay_chain.get_storage_by_key(hash, QUEUE_STATUS).await.ok()?;
-	let queue_status = queue_status_storage
-		.map(|raw| <QueueStatus>::decode(&mut &raw[..]))
-		.transpose()
-		.ok()?;
+	public: &[u8],
+) -> Result<u128, Box<dyn Error>> {
+	let account_id: [u8; 32] = public.try_into().map_err(|_| "Invalid relay chain account")?;
 
-	let active_config_storage = relay_chain.get_storage_by_key(hash, ACTIVE_CONFIG).await.ok()?;
-	let active_config = active_config_storage
-		.map(|raw| <HostConfiguration<u32>>::decode(&mut &raw[..]))
-		.transpose()
-		.ok()?;
+	let Some(raw) = relay_chain.get_storage_by_key(hash, &account(account_id)).await? else {
+		// The account doesn't exist on the relay chain.
+		return Ok(0)
+	};
 
-	if queue_status.is_some() && active_config.is_some() {
-		let traffic = queue_status.expect("We ensured spot_traffic is Some above; qed").traffic;
-		let active_config = active_config.expect("We ensured active_config is Some above; qed");
-		let spot_price = traffic.0.saturating_mul(
-			active_config.scheduler_params.on_demand_base_fee.saturated_into::<u128>(),
-		);
-		Some(Balance::from(spot_price))
-	} else {
-		None
-	}
+	let account_info = metadata.decode_storage(SYSTEM_PALLET, ACCOUNT, &raw)?;
+	let free = account_info
+		.at("data")
+		.and_then(|data| data.at("free"))
+		.and_then(|free| free.as_u128())
+		.ok_or("Failed to decode relay chain account balance")?;
+
+	Ok(free)
 }
 
 /// Is this a parathread?
@@ -163,46 +281,111 @@ pub async fn is_parathread(
 	Ok(is_parathread)
 }
 
-/// Checks if there are any cores allocated to on-demand.
-pub async fn on_demand_cores_available(
+/// Assignments scheduled on a core, expressed as the range of relay chain blocks they cover.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoreQueue {
+	/// The relay chain block number at which the first queued assignment starts.
+	pub first: u32,
+	/// The relay chain block number at which the last queued assignment starts.
+	pub last: u32,
+}
+
+/// A core that is, at least partially, assigned to the on-demand pool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolCore {
+	/// Index of the core.
+	pub core: CoreIndex,
+	/// The fraction of the core assigned to the pool, in parts of 57600.
+	pub parts: u16,
+	/// The assignments queued on the core.
+	pub queue: Option<CoreQueue>,
+}
+
+/// The on-demand capacity provided by the relay chain.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct OnDemandCapacity {
+	/// All the cores currently serving the on-demand pool.
+	pub cores: Vec<PoolCore>,
+}
+
+impl OnDemandCapacity {
+	/// Whether any core is currently serving the on-demand pool.
+	pub fn is_available(&self) -> bool {
+		!self.cores.is_empty()
+	}
+
+	/// The sum of all core fractions assigned to the pool, in parts of 57600.
+	pub fn total_parts(&self) -> u32 {
+		self.cores.iter().map(|core| core.parts as u32).sum()
+	}
+}
+
+/// Returns all the cores that are currently assigned to the on-demand pool.
+pub async fn on_demand_capacity(
 	relay_chain: &(impl RelayChainInterface + Clone),
+	metadata: &RelayMetadata,
 	hash: H256,
-) -> Option<bool> {
-	let active_config_storage = relay_chain.get_storage_by_key(hash, ACTIVE_CONFIG).await.ok()?;
+) -> Result<OnDemandCapacity, Box<dyn Error>> {
+	let active_config_storage = relay_chain.get_storage_by_key(hash, ACTIVE_CONFIG).await?;
 
 	let active_config = active_config_storage
 		.map(|raw| <HostConfiguration<u32>>::decode(&mut &raw[..]))
-		.transpose()
-		.ok()?;
+		.transpose()?
+		.ok_or("Active host configuration not found")?;
 
-	let active_config = active_config?;
+	let mut capacity = OnDemandCapacity::default();
 
 	for core in 0..active_config.scheduler_params.num_cores {
-		let core_descriptor_storage = relay_chain
-			.get_storage_by_key(hash, &core_descriptor(CoreIndex(core)))
-			.await
-			.ok()?;
-
-		let core_descriptor = core_descriptor_storage
-			.map(|raw| <CoreDescriptor<u32>>::decode(&mut &raw[..]))
-			.transpose()
-			.ok()?;
-
-		if let Some(descriptor) = core_descriptor {
-			if let Some(work) = descriptor.current_work {
-				let available_core = work
-					.assignments
-					.into_iter()
-					.position(|assignment| match assignment {
-						(CoreAssignment::Pool, _) => true,
-						_ => false,
-					})
-					.is_some();
-
-				return Some(available_core)
-			}
+		let core_descriptor_storage =
+			relay_chain.get_storage_by_key(hash, &core_descriptor(CoreIndex(core))).await?;
+
+		let Some(descriptor) = core_descriptor_storage
+			.map(|raw| metadata.decode_storage(CORETIME_ASSIGNMENT_PALLET, CORE_DESCRIPTORS, &raw))
+			.transpose()?
+		else {
+			continue
+		};
+
+		if let Some(pool_core) = pool_core(CoreIndex(core), &descriptor) {
+			capacity.cores.push(pool_core);
 		}
 	}
 
-	Some(false)
+	Ok(capacity)
+}
+
+/// Returns the pool assignments of a core, given its decoded `CoreDescriptor`.
+///
+/// Returns `None` if no part of the core is currently assigned to the pool.
+pub fn pool_core(core: CoreIndex, descriptor: &DecodedValue) -> Option<PoolCore> {
+	let work = descriptor.at("current_work").and_then(as_option)?;
+
+	// A core can have multiple assignments to the pool, hence we are summing them up.
+	let parts = work
+		.at("assignments")
+		.map(as_sequence)
+		.unwrap_or_default()
+		.into_iter()
+		.filter(|assignment| assignment.at(0).and_then(variant_name) == Some("Pool"))
+		// `ratio` is a `PartsOf57600`, i.e. a wrapper around its inner `u16` value.
+		.filter_map(|assignment| assignment.at(1)?.at("ratio")?.at(0)?.as_u128())
+		.fold(0u16, |total, ratio| total.saturating_add(ratio as u16));
+
+	if parts == 0 {
+		return None
+	}
+
+	let queue = descriptor.at("queue").and_then(as_option).and_then(|queue| {
+		Some(CoreQueue {
+			first: queue.at("first")?.as_u128()? as u32,
+			last: queue.at("last")?.as_u128()? as u32,
+		})
+	});
+
+	Some(PoolCore { core, parts, queue })
+}
+
+/// Returns the storage key of a storage value, or the prefix of a storage map.
+pub fn storage_value_key(pallet: &str, entry: &str) -> Vec<u8> {
+	[twox_128(pallet.as_bytes()), twox_128(entry.as_bytes())].concat()
 }
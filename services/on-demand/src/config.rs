@@ -1,25 +1,38 @@
 //! This file contains all the configuration related traits.
 
-use crate::RelayBlockNumber;
-use codec::{Codec, Decode};
-use cumulus_primitives_core::ConsensusEngineId;
+use crate::{
+	chain::storage_value_key,
+	criteria::percentage,
+	election::{AuraElection, PlacerElection, RoundRobin},
+	settings::Settings,
+	RelayBlockNumber,
+};
+use codec::{Codec, Decode, Encode};
+use cumulus_primitives_core::{ParaId, PersistedValidationData};
 use cumulus_relay_chain_interface::RelayChainInterface;
 use on_demand_primitives::ThresholdParameterT;
-use sc_client_api::UsageProvider;
+use sc_client_api::{Backend as BackendT, BlockchainEvents, StorageProvider, UsageProvider};
 use sc_service::Arc;
 use sc_transaction_pool_api::MaintainedTransactionPool;
 use sp_api::ProvideRuntimeApi;
 use sp_application_crypto::RuntimeAppPublic;
-use sp_consensus_aura::{AuraApi, Slot, AURA_ENGINE_ID};
-use sp_core::{crypto::Pair as PairT, H256};
+use sp_consensus_aura::AuraApi;
+use sp_core::{crypto::Pair as PairT, hashing::twox_64, storage::StorageKey, H256};
 use sp_runtime::{
 	generic::BlockId,
 	traits::{
-		AtLeast32BitUnsigned, Block as BlockT, Debug, Header, MaybeDisplay, Member, PhantomData,
+		AtLeast32BitUnsigned, Block as BlockT, Debug, Header, MaybeDisplay, Member, OpaqueKeys,
+		PhantomData,
 	},
 };
 use std::{error::Error, fmt::Display, future::Future, pin::Pin, time::Duration};
 
+const COLLATOR_SELECTION_PALLET: &str = "CollatorSelection";
+const INVULNERABLES: &str = "Invulnerables";
+const CANDIDATE_LIST: &str = "CandidateList";
+const SESSION_PALLET: &str = "Session";
+const NEXT_KEYS: &str = "NextKeys";
+
 pub trait OnDemandConfig {
 	/// Custom order placement criteria.
 	type OrderPlacementCriteria: OrderCriteria;
@@ -31,10 +44,18 @@ pub trait OnDemandConfig {
 	type Block: BlockT;
 
 	/// Relay chain.
-	type R: RelayChainInterface + Clone;
+	type R: RelayChainInterface + Clone + 'static;
+
+	/// Parachain backend.
+	type Backend: BackendT<Self::Block>;
 
 	/// Parachain.
-	type P: ProvideRuntimeApi<Self::Block> + UsageProvider<Self::Block> + Send + Sync;
+	type P: ProvideRuntimeApi<Self::Block>
+		+ UsageProvider<Self::Block>
+		+ StorageProvider<Self::Block, Self::Backend>
+		+ BlockchainEvents<Self::Block>
+		+ Send
+		+ Sync;
 
 	/// Extrinsic pool.
 	type ExPool: MaintainedTransactionPool<Block = Self::Block, Hash = <Self::Block as BlockT>::Hash>
@@ -54,39 +75,199 @@ pub trait OnDemandConfig {
 	/// On-demand pallet threshold parameter.
 	type ThresholdParameter: ThresholdParameterT;
 
-	type OrderPlacerFuture: Future<Output = Result<Self::AuthorPub, Box<dyn Error>>> + Send;
+	/// Future resolving to the elected order placer.
+	///
+	/// The future must not borrow from the arguments of `order_placer`, so that no state has to
+	/// outlive a single relay chain block.
+	type OrderPlacerFuture: Future<Output = Result<Self::AuthorPub, Box<dyn Error>>>
+		+ Send
+		+ 'static;
+
+	/// Returns the authorities that are allowed to place orders, in the order in which they
+	/// take over if the elected order placer can't afford an order.
+	fn authorities(
+		para: &Self::P,
+		para_hash: <Self::Block as BlockT>::Hash,
+	) -> Result<Vec<Self::AuthorPub>, Box<dyn Error>>;
 
+	/// Elects the collator responsible for placing an order.
+	///
+	/// The relay chain handle is passed by value, allowing the returned future to use it.
 	fn order_placer(
-		relay_chain: &'static Self::R,
+		relay_chain: Self::R,
 		para: &Self::P,
 		relay_hash: H256,
+		relay_height: RelayBlockNumber,
 		para_header: <Self::Block as BlockT>::Header,
 		relay_chain_slot_duration: Duration,
 	) -> Self::OrderPlacerFuture;
 }
 
+/// Information available to the order placement criteria.
+pub struct OrderContext<'a, P, ExPool, Balance, Threshold> {
+	/// The parachain client.
+	pub parachain: &'a P,
+	/// The transaction pool of the parachain.
+	pub transaction_pool: Arc<ExPool>,
+	/// The relay chain.
+	pub relay_chain: &'a dyn RelayChainInterface,
+	/// The id of the parachain.
+	pub para_id: ParaId,
+	/// Hash of the relay chain block the order would be based on.
+	pub relay_hash: H256,
+	/// Number of the relay chain block the order would be based on.
+	pub relay_height: RelayBlockNumber,
+	/// The validation data of the parachain at `relay_hash`.
+	pub validation_data: &'a PersistedValidationData,
+	/// The amount the order would be placed for.
+	pub spot_price: u128,
+	/// The number of relay chain blocks since the parachain head last changed.
+	pub idle_relay_blocks: RelayBlockNumber,
+	/// The on-demand settings of the best parachain block.
+	pub settings: &'a Settings<Balance, Threshold>,
+}
+
+/// The criteria a decision is based on, used as metric label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReasonCode {
+	/// Fees of the ready transactions.
+	Fees,
+	/// Number of ready transactions.
+	TransactionCount,
+	/// Weight of the ready transactions.
+	PendingWeight,
+	/// How full a block of the ready transactions would be.
+	BlockFullness,
+	/// Relay chain blocks since the parachain head changed.
+	IdleTime,
+	/// Spot price of an order.
+	Price,
+	/// Number of queued inbound messages.
+	InboundMessages,
+	/// Age of the oldest queued inbound message.
+	InboundMessageAge,
+	/// The parachain is kept alive after having been idle for too long.
+	KeepAlive,
+	/// Criteria specific to the parachain.
+	Custom,
+}
+
+impl ReasonCode {
+	/// Returns the metric label of the code.
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Self::Fees => "fees",
+			Self::TransactionCount => "transaction_count",
+			Self::PendingWeight => "pending_weight",
+			Self::BlockFullness => "block_fullness",
+			Self::IdleTime => "idle_time",
+			Self::Price => "price",
+			Self::InboundMessages => "inbound_messages",
+			Self::InboundMessageAge => "inbound_message_age",
+			Self::KeepAlive => "keep_alive",
+			Self::Custom => "custom",
+		}
+	}
+}
+
+/// A value measured by the order placement criteria, compared against its threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Measurement {
+	/// What got measured.
+	pub code: ReasonCode,
+	/// The measured value.
+	pub value: u128,
+	/// The threshold the value is compared against.
+	pub threshold: u128,
+}
+
+/// The outcome of evaluating order placement criteria.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decision {
+	/// Whether an order should be placed.
+	pub place_order: bool,
+	/// The criteria which determined the decision.
+	pub code: ReasonCode,
+	/// Human readable explanation of the decision.
+	pub reason: String,
+	/// The values measured by all the evaluated criteria.
+	pub measurements: Vec<Measurement>,
+}
+
+impl Decision {
+	/// An order should be placed.
+	pub fn place(code: ReasonCode, reason: impl Into<String>) -> Self {
+		Self { place_order: true, code, reason: reason.into(), measurements: Vec::new() }
+	}
+
+	/// No order should be placed.
+	pub fn skip(code: ReasonCode, reason: impl Into<String>) -> Self {
+		Self { place_order: false, code, reason: reason.into(), measurements: Vec::new() }
+	}
+
+	/// Records a value measured to reach the decision.
+	pub fn measured(mut self, code: ReasonCode, value: u128, threshold: u128) -> Self {
+		self.measurements.push(Measurement { code, value, threshold });
+		self
+	}
+
+	/// Returns the first value measured for `code` as a percentage of its threshold.
+	pub fn progress(&self, code: ReasonCode) -> Option<u32> {
+		let measurement = self.measurements.iter().find(|m| m.code == code)?;
+		Some(percentage(measurement.value, measurement.threshold))
+	}
+}
+
+/// The order context of the given criteria.
+pub type CriteriaContext<'a, C> = OrderContext<
+	'a,
+	<C as OrderCriteria>::P,
+	<C as OrderCriteria>::ExPool,
+	<C as OrderCriteria>::Balance,
+	<C as OrderCriteria>::ThresholdParameter,
+>;
+
+/// Future resolving to the decision of the order placement criteria.
+pub type DecisionFuture<'a> = Pin<Box<dyn Future<Output = Decision> + Send + 'a>>;
+
 pub trait OrderCriteria {
 	type Block: BlockT;
-	type P: ProvideRuntimeApi<Self::Block> + UsageProvider<Self::Block>;
+	type P: ProvideRuntimeApi<Self::Block> + UsageProvider<Self::Block> + Send + Sync;
 	type ExPool: MaintainedTransactionPool<Block = Self::Block, Hash = <Self::Block as BlockT>::Hash>
 		+ 'static;
+	type Balance: Sync;
+	type ThresholdParameter: Sync;
 
-	/// Returns true or false depending on whether an order should be placed.
-	fn should_place_order(
-		parachain: &Self::P,
-		transaction_pool: Arc<Self::ExPool>,
-		height: RelayBlockNumber,
-	) -> bool;
+	/// Decides whether an order should be placed.
+	fn should_place_order<'a>(ctx: &'a CriteriaContext<'a, Self>) -> DecisionFuture<'a>;
+
+	/// Returns the progress towards the order placement threshold as a percentage.
+	///
+	/// The progress is derived from the values measured for the `decision`, rather than measuring
+	/// them again. This is only used for reporting. Criteria without a threshold can rely on the
+	/// default implementation.
+	fn threshold_progress(_decision: &Decision) -> Option<u32> {
+		None
+	}
 }
 
-pub struct OnDemandAura<R, P, Block, Pair, ExPool, Balance, C, T>(
-	PhantomData<(R, P, Block, Pair, ExPool, Balance, C, T)>,
+/// On-demand configuration for parachains whose authorities are the Aura authorities.
+///
+/// By default the author of the parent parachain block is elected to place orders.
+pub struct OnDemandAura<R, P, Block, Backend, Pair, ExPool, Balance, C, T, E = AuraElection>(
+	PhantomData<(R, P, Block, Backend, Pair, ExPool, Balance, C, T, E)>,
 );
-impl<P, R, Block, Pair, ExPool, Balance, Criteria, Threshold> OnDemandConfig
-	for OnDemandAura<R, P, Block, Pair, ExPool, Balance, Criteria, Threshold>
+impl<P, R, Block, Backend, Pair, ExPool, Balance, Criteria, Threshold, Election> OnDemandConfig
+	for OnDemandAura<R, P, Block, Backend, Pair, ExPool, Balance, Criteria, Threshold, Election>
 where
 	R: RelayChainInterface + Clone + Sync + Send,
-	P: ProvideRuntimeApi<Block> + UsageProvider<Block> + Sync + Send,
+	P: ProvideRuntimeApi<Block>
+		+ UsageProvider<Block>
+		+ StorageProvider<Block, Backend>
+		+ BlockchainEvents<Block>
+		+ Sync
+		+ Send,
+	Backend: BackendT<Block>,
 	P::Api: AuraApi<Block, Pair::Public>,
 	Criteria: OrderCriteria,
 	Pair: PairT + 'static,
@@ -103,9 +284,11 @@ where
 	Pair::Public: RuntimeAppPublic + Display + Member + Codec,
 	Block: BlockT<Hash = H256>,
 	Threshold: ThresholdParameterT,
+	Election: PlacerElection<Pair::Public>,
 {
 	type P = P;
 	type R = R;
+	type Backend = Backend;
 
 	type OrderPlacementCriteria = Criteria;
 	type AuthorPub = Pair::Public;
@@ -118,42 +301,225 @@ where
 	type OrderPlacerFuture =
 		Pin<Box<dyn Future<Output = Result<Self::AuthorPub, Box<dyn Error>>> + Send>>;
 
+	fn authorities(para: &P, para_hash: H256) -> Result<Vec<Self::AuthorPub>, Box<dyn Error>> {
+		Ok(para.runtime_api().authorities(para_hash)?)
+	}
+
 	fn order_placer(
-		relay_chain: &'static R,
+		_relay_chain: R,
 		para: &P,
-		relay_hash: H256,
+		_relay_hash: H256,
+		relay_height: RelayBlockNumber,
 		para_header: <Self::Block as BlockT>::Header,
-		relay_chain_slot_duration: Duration,
+		_relay_chain_slot_duration: Duration,
 	) -> Self::OrderPlacerFuture {
-		let para_hash = para_header.hash();
-		let authorities_result = para.runtime_api().authorities(para_hash).map_err(Box::new);
+		// The error isn't `Send`, so it can't be held across the future.
+		let authorities = Self::authorities(para, para_header.hash()).map_err(|e| e.to_string());
 
-		Box::pin(async move {
-			let authorities = authorities_result?;
+		elect_order_placer::<_, _, Election>(authorities, para_header, relay_height)
+	}
+}
 
-			let author_index = find_author(
-				para_header.digest().logs().iter().filter_map(|d| d.as_pre_runtime()),
-				authorities.len(),
-			)
-			.ok_or("Could not find aura author index")?;
+/// On-demand configuration for parachains using `pallet-collator-selection`.
+///
+/// The authorities are the invulnerables followed by the candidates, read from the parachain
+/// state. Collators are identified by the `Public` key in their next session `Keys`; collators
+/// without session keys can't author blocks and are skipped. `ParaBalance` is the balance type of
+/// the candidate deposits.
+///
+/// By default the order placer rotates every relay chain block.
+pub struct OnDemandCollatorSelection<
+	R,
+	P,
+	Block,
+	Backend,
+	AccountId,
+	ParaBalance,
+	Keys,
+	Public,
+	ExPool,
+	Balance,
+	C,
+	T,
+	E = RoundRobin,
+>(
+	PhantomData<(
+		R,
+		P,
+		Block,
+		Backend,
+		AccountId,
+		ParaBalance,
+		Keys,
+		Public,
+		ExPool,
+		Balance,
+		C,
+		T,
+		E,
+	)>,
+);
+impl<
+		P,
+		R,
+		Block,
+		Backend,
+		AccountId,
+		ParaBalance,
+		Keys,
+		Public,
+		ExPool,
+		Balance,
+		Criteria,
+		Threshold,
+		Election,
+	> OnDemandConfig
+	for OnDemandCollatorSelection<
+		R,
+		P,
+		Block,
+		Backend,
+		AccountId,
+		ParaBalance,
+		Keys,
+		Public,
+		ExPool,
+		Balance,
+		Criteria,
+		Threshold,
+		Election,
+	>
+where
+	R: RelayChainInterface + Clone + Sync + Send,
+	P: ProvideRuntimeApi<Block>
+		+ UsageProvider<Block>
+		+ StorageProvider<Block, Backend>
+		+ BlockchainEvents<Block>
+		+ Sync
+		+ Send,
+	Backend: BackendT<Block>,
+	Criteria: OrderCriteria,
+	ExPool: MaintainedTransactionPool<Block = Block, Hash = <Block as BlockT>::Hash> + 'static,
+	Balance: Codec
+		+ MaybeDisplay
+		+ 'static
+		+ Debug
+		+ Send
+		+ Into<u128>
+		+ AtLeast32BitUnsigned
+		+ Copy
+		+ From<u128>,
+	AccountId: Codec,
+	ParaBalance: Decode,
+	Keys: OpaqueKeys + Decode,
+	Public: RuntimeAppPublic + Display + Member + Codec,
+	Block: BlockT<Hash = H256>,
+	Threshold: ThresholdParameterT,
+	Election: PlacerElection<Public>,
+{
+	type P = P;
+	type R = R;
+	type Backend = Backend;
 
-			let author = authorities.get(author_index as usize).ok_or("Invalid aura index")?;
-			Ok(author.clone())
+	type OrderPlacementCriteria = Criteria;
+	type AuthorPub = Public;
+	type Block = Block;
+
+	type ExPool = ExPool;
+	type Balance = Balance;
+	type ThresholdParameter = Threshold;
+
+	type OrderPlacerFuture =
+		Pin<Box<dyn Future<Output = Result<Self::AuthorPub, Box<dyn Error>>> + Send>>;
+
+	fn authorities(para: &P, para_hash: H256) -> Result<Vec<Self::AuthorPub>, Box<dyn Error>> {
+		collator_selection_authorities::<AccountId, ParaBalance, Keys, Public>(|key| {
+			Ok(para.storage(para_hash, &StorageKey(key))?.map(|data| data.0))
 		})
 	}
+
+	fn order_placer(
+		_relay_chain: R,
+		para: &P,
+		_relay_hash: H256,
+		relay_height: RelayBlockNumber,
+		para_header: <Self::Block as BlockT>::Header,
+		_relay_chain_slot_duration: Duration,
+	) -> Self::OrderPlacerFuture {
+		// The error isn't `Send`, so it can't be held across the future.
+		let authorities = Self::authorities(para, para_header.hash()).map_err(|e| e.to_string());
+
+		elect_order_placer::<_, _, Election>(authorities, para_header, relay_height)
+	}
 }
 
-fn find_author<'a, I>(digests: I, authorities_len: usize) -> Option<u32>
+/// Elects the order placer among the `authorities` of the parachain.
+pub(crate) fn elect_order_placer<Public, ParaHeader, Election>(
+	authorities: Result<Vec<Public>, String>,
+	para_header: ParaHeader,
+	relay_height: RelayBlockNumber,
+) -> Pin<Box<dyn Future<Output = Result<Public, Box<dyn Error>>> + Send>>
 where
-	I: 'a + IntoIterator<Item = (ConsensusEngineId, &'a [u8])>,
+	Public: Send + 'static,
+	ParaHeader: Header + 'static,
+	Election: PlacerElection<Public>,
 {
-	for (id, mut data) in digests.into_iter() {
-		if id == AURA_ENGINE_ID {
-			let slot = Slot::decode(&mut data).ok()?;
-			let author_index = *slot % authorities_len as u64;
-			return Some(author_index as u32)
+	Box::pin(async move {
+		let authorities = authorities?;
+		let author = Election::elect(&authorities, &para_header, relay_height)
+			.ok_or("Could not elect an order placer")?;
+		Ok(author)
+	})
+}
+
+/// A candidate of `pallet-collator-selection`.
+#[derive(Decode)]
+struct CandidateInfo<AccountId, Balance> {
+	who: AccountId,
+	_deposit: Balance,
+}
+
+/// Returns the keys of the `pallet-collator-selection` invulnerables followed by the candidates.
+///
+/// The keys are taken from the next session keys of the collators. Collators without session keys
+/// are skipped.
+pub(crate) fn collator_selection_authorities<AccountId, ParaBalance, Keys, Public>(
+	read: impl Fn(Vec<u8>) -> Result<Option<Vec<u8>>, Box<dyn Error>>,
+) -> Result<Vec<Public>, Box<dyn Error>>
+where
+	AccountId: Codec,
+	ParaBalance: Decode,
+	Keys: OpaqueKeys + Decode,
+	Public: RuntimeAppPublic + Decode,
+{
+	let mut collators: Vec<AccountId> =
+		read_value(&read, storage_value_key(COLLATOR_SELECTION_PALLET, INVULNERABLES))?
+			.unwrap_or_default();
+	let candidates: Vec<CandidateInfo<AccountId, ParaBalance>> =
+		read_value(&read, storage_value_key(COLLATOR_SELECTION_PALLET, CANDIDATE_LIST))?
+			.unwrap_or_default();
+	collators.extend(candidates.into_iter().map(|candidate| candidate.who));
+
+	let mut authorities = Vec::new();
+	for collator in collators {
+		let mut key = storage_value_key(SESSION_PALLET, NEXT_KEYS);
+		let encoded = collator.encode();
+		key.extend(twox_64(&encoded));
+		key.extend(encoded);
+
+		let keys: Option<Keys> = read_value(&read, key)?;
+		if let Some(authority) = keys.and_then(|keys| keys.get(Public::ID)) {
+			authorities.push(authority);
 		}
 	}
 
-	None
+	Ok(authorities)
+}
+
+/// Reads and decodes a storage value of the parachain.
+fn read_value<T: Decode>(
+	read: &impl Fn(Vec<u8>) -> Result<Option<Vec<u8>>, Box<dyn Error>>,
+	key: Vec<u8>,
+) -> Result<Option<T>, Box<dyn Error>> {
+	Ok(read(key)?.map(|raw| T::decode(&mut &raw[..])).transpose()?)
 }
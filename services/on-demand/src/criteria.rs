@@ -0,0 +1,568 @@
+//! Reusable order placement criteria and combinators to compose them.
+//!
+//! Criteria read their thresholds from the runtime settings of the
+//! [`OrderContext`](crate::config::OrderContext), which are kept up to date from the parachain
+//! events. Thresholds are looked up by their [`ThresholdKind`]:
+//!
+//! - [`FeeThreshold`]: the threshold parameter of the runtime.
+//! - [`ReadyTransactions`]: [`ThresholdKind::TransactionCount`].
+//! - [`PendingWeight`]: [`ThresholdKind::PendingWeight`].
+//! - [`BlockFullness`]: [`ThresholdKind::BlockFullness`].
+//! - [`TimeSinceLastBlock`]: [`ThresholdKind::IdleTime`].
+//! - [`SpotPriceBelow`]: [`ThresholdKind::Price`].
+//!
+//! Criteria whose threshold isn't set in the runtime are never met, e.g.:
+//!
+//! ```ignore
+//! type Criteria = Or<
+//! 	FeeThreshold<Block, Client, Pool, Balance>,
+//! 	And<
+//! 		ReadyTransactions<Block, Client, Pool, Balance, Balance>,
+//! 		TimeSinceLastBlock<Block, Client, Pool, Balance, Balance>,
+//! 	>,
+//! >;
+//! ```
+//!
+//! Criteria which need to read the relay chain state are evaluated asynchronously, using the
+//! relay chain interface of the context.
+
+use crate::{
+	config::{CriteriaContext, Decision, DecisionFuture, OrderCriteria, ReasonCode},
+	settings::Settings,
+	RelayBlockNumber,
+};
+use codec::{Codec, Encode};
+use on_demand_primitives::ThresholdKind;
+use pallet_transaction_payment_rpc_runtime_api::TransactionPaymentApi;
+use sc_client_api::UsageProvider;
+use sc_transaction_pool_api::{InPoolTransaction, MaintainedTransactionPool, TransactionPool};
+use sp_api::ProvideRuntimeApi;
+use sp_runtime::traits::{
+	AtLeast32BitUnsigned, Block as BlockT, Get, MaybeDisplay, PhantomData, Saturating, Zero,
+};
+use std::{future, sync::Arc};
+
+/// Met when both criteria are met.
+pub struct And<A, B>(PhantomData<(A, B)>);
+
+impl<A, B> OrderCriteria for And<A, B>
+where
+	A: OrderCriteria,
+	B: OrderCriteria<
+		Block = A::Block,
+		P = A::P,
+		ExPool = A::ExPool,
+		Balance = A::Balance,
+		ThresholdParameter = A::ThresholdParameter,
+	>,
+{
+	type Block = A::Block;
+	type P = A::P;
+	type ExPool = A::ExPool;
+	type Balance = A::Balance;
+	type ThresholdParameter = A::ThresholdParameter;
+
+	fn should_place_order<'a>(ctx: &'a CriteriaContext<'a, Self>) -> DecisionFuture<'a> {
+		Box::pin(async move {
+			let a = A::should_place_order(ctx).await;
+			if !a.place_order {
+				return a
+			}
+
+			let b = B::should_place_order(ctx).await;
+			Decision {
+				place_order: b.place_order,
+				code: b.code,
+				reason: format!("{} and {}", a.reason, b.reason),
+				measurements: [a.measurements, b.measurements].concat(),
+			}
+		})
+	}
+
+	fn threshold_progress(decision: &Decision) -> Option<u32> {
+		let a = A::threshold_progress(decision);
+		let b = B::threshold_progress(decision);
+		// The least progressed criteria determines the overall progress.
+		match (a, b) {
+			(Some(a), Some(b)) => Some(a.min(b)),
+			(a, b) => a.or(b),
+		}
+	}
+}
+
+/// Met when any of the criteria is met.
+pub struct Or<A, B>(PhantomData<(A, B)>);
+
+impl<A, B> OrderCriteria for Or<A, B>
+where
+	A: OrderCriteria,
+	B: OrderCriteria<
+		Block = A::Block,
+		P = A::P,
+		ExPool = A::ExPool,
+		Balance = A::Balance,
+		ThresholdParameter = A::ThresholdParameter,
+	>,
+{
+	type Block = A::Block;
+	type P = A::P;
+	type ExPool = A::ExPool;
+	type Balance = A::Balance;
+	type ThresholdParameter = A::ThresholdParameter;
+
+	fn should_place_order<'a>(ctx: &'a CriteriaContext<'a, Self>) -> DecisionFuture<'a> {
+		Box::pin(async move {
+			let a = A::should_place_order(ctx).await;
+			if a.place_order {
+				return a
+			}
+
+			let b = B::should_place_order(ctx).await;
+			Decision {
+				place_order: b.place_order,
+				code: b.code,
+				reason: if b.place_order {
+					b.reason
+				} else {
+					format!("{} and {}", a.reason, b.reason)
+				},
+				measurements: [a.measurements, b.measurements].concat(),
+			}
+		})
+	}
+
+	fn threshold_progress(decision: &Decision) -> Option<u32> {
+		let a = A::threshold_progress(decision);
+		let b = B::threshold_progress(decision);
+		// The most progressed criteria determines the overall progress.
+		match (a, b) {
+			(Some(a), Some(b)) => Some(a.max(b)),
+			(a, b) => a.or(b),
+		}
+	}
+}
+
+/// Met when the inner criteria isn't.
+pub struct Not<A>(PhantomData<A>);
+
+impl<A: OrderCriteria> OrderCriteria for Not<A> {
+	type Block = A::Block;
+	type P = A::P;
+	type ExPool = A::ExPool;
+	type Balance = A::Balance;
+	type ThresholdParameter = A::ThresholdParameter;
+
+	fn should_place_order<'a>(ctx: &'a CriteriaContext<'a, Self>) -> DecisionFuture<'a> {
+		Box::pin(async move {
+			let inner = A::should_place_order(ctx).await;
+			Decision {
+				place_order: !inner.place_order,
+				reason: format!("not: {}", inner.reason),
+				..inner
+			}
+		})
+	}
+}
+
+/// Met when the fees of the ready transactions reach the threshold parameter of the runtime.
+pub struct FeeThreshold<Block, P, ExPool, Balance>(PhantomData<(Block, P, ExPool, Balance)>);
+
+impl<Block, P, ExPool, Balance> FeeThreshold<Block, P, ExPool, Balance>
+where
+	Block: BlockT,
+	P: ProvideRuntimeApi<Block> + UsageProvider<Block>,
+	P::Api: TransactionPaymentApi<Block, Balance>,
+	ExPool: MaintainedTransactionPool<Block = Block, Hash = Block::Hash> + 'static,
+	Balance: Codec + MaybeDisplay + AtLeast32BitUnsigned + Copy + Into<u128>,
+{
+	/// Sums up the fees of all the ready transactions in the pool.
+	pub fn pending_fees(parachain: &P, transaction_pool: Arc<ExPool>) -> Balance {
+		let block_hash = parachain.usage_info().chain.best_hash;
+		let api = parachain.runtime_api();
+
+		transaction_pool.ready().fold(Balance::zero(), |total, tx| {
+			let len = tx.data().encoded_size() as u32;
+			match api.query_fee_details(block_hash, tx.data().clone(), len) {
+				Ok(details) => total.saturating_add(details.final_fee()),
+				Err(_) => total,
+			}
+		})
+	}
+}
+
+impl<Block, P, ExPool, Balance> OrderCriteria for FeeThreshold<Block, P, ExPool, Balance>
+where
+	Block: BlockT,
+	P: ProvideRuntimeApi<Block> + UsageProvider<Block> + Send + Sync,
+	P::Api: TransactionPaymentApi<Block, Balance>,
+	ExPool: MaintainedTransactionPool<Block = Block, Hash = Block::Hash> + 'static,
+	Balance: Codec + MaybeDisplay + AtLeast32BitUnsigned + Copy + Into<u128> + Sync,
+{
+	type Block = Block;
+	type P = P;
+	type ExPool = ExPool;
+	type Balance = Balance;
+	type ThresholdParameter = Balance;
+
+	fn should_place_order<'a>(ctx: &'a CriteriaContext<'a, Self>) -> DecisionFuture<'a> {
+		let fee_threshold = ctx.settings.threshold_parameter;
+		let total_fees = Self::pending_fees(ctx.parachain, ctx.transaction_pool.clone());
+		let reason = format!("pending fees {} of threshold {}", total_fees, fee_threshold);
+		let decision = reached(ReasonCode::Fees, total_fees.into(), fee_threshold.into(), reason);
+
+		Box::pin(future::ready(decision))
+	}
+
+	fn threshold_progress(decision: &Decision) -> Option<u32> {
+		decision.progress(ReasonCode::Fees)
+	}
+}
+
+/// Met when the number of ready transactions in the pool reaches the `TransactionCount`
+/// threshold of the runtime.
+pub struct ReadyTransactions<Block, P, ExPool, Balance, Threshold>(
+	PhantomData<(Block, P, ExPool, Balance, Threshold)>,
+);
+
+impl<Block, P, ExPool, Balance, Threshold> OrderCriteria
+	for ReadyTransactions<Block, P, ExPool, Balance, Threshold>
+where
+	Block: BlockT,
+	P: ProvideRuntimeApi<Block> + UsageProvider<Block> + Send + Sync,
+	ExPool: MaintainedTransactionPool<Block = Block, Hash = Block::Hash> + 'static,
+	Balance: Sync,
+	Threshold: Clone + Into<u128> + Sync,
+{
+	type Block = Block;
+	type P = P;
+	type ExPool = ExPool;
+	type Balance = Balance;
+	type ThresholdParameter = Threshold;
+
+	fn should_place_order<'a>(ctx: &'a CriteriaContext<'a, Self>) -> DecisionFuture<'a> {
+		let decision = match threshold(ctx.settings, ThresholdKind::TransactionCount) {
+			Some(count) => {
+				let ready = ctx.transaction_pool.status().ready as u128;
+				let reason = format!("{} of {} ready transactions", ready, count);
+				reached(ReasonCode::TransactionCount, ready, count, reason)
+			},
+			None =>
+				Decision::skip(ReasonCode::TransactionCount, "transaction count threshold not set"),
+		};
+
+		Box::pin(future::ready(decision))
+	}
+
+	fn threshold_progress(decision: &Decision) -> Option<u32> {
+		decision.progress(ReasonCode::TransactionCount)
+	}
+}
+
+/// Met when the `ref_time` of the ready transactions in the pool reaches the `PendingWeight`
+/// threshold of the runtime.
+pub struct PendingWeight<Block, P, ExPool, Balance, Threshold>(
+	PhantomData<(Block, P, ExPool, Balance, Threshold)>,
+);
+
+impl<Block, P, ExPool, Balance, Threshold> PendingWeight<Block, P, ExPool, Balance, Threshold>
+where
+	Block: BlockT,
+	P: ProvideRuntimeApi<Block> + UsageProvider<Block>,
+	P::Api: TransactionPaymentApi<Block, Balance>,
+	ExPool: MaintainedTransactionPool<Block = Block, Hash = Block::Hash> + 'static,
+	Balance: Codec + MaybeDisplay,
+{
+	/// Sums up the `ref_time` of all the ready transactions in the pool.
+	pub fn pending_weight(parachain: &P, transaction_pool: Arc<ExPool>) -> u64 {
+		let block_hash = parachain.usage_info().chain.best_hash;
+		let api = parachain.runtime_api();
+
+		transaction_pool.ready().fold(0u64, |total, tx| {
+			let len = tx.data().encoded_size() as u32;
+			match api.query_info(block_hash, tx.data().clone(), len) {
+				Ok(info) => total.saturating_add(info.weight.ref_time()),
+				Err(_) => total,
+			}
+		})
+	}
+}
+
+impl<Block, P, ExPool, Balance, Threshold> OrderCriteria
+	for PendingWeight<Block, P, ExPool, Balance, Threshold>
+where
+	Block: BlockT,
+	P: ProvideRuntimeApi<Block> + UsageProvider<Block> + Send + Sync,
+	P::Api: TransactionPaymentApi<Block, Balance>,
+	ExPool: MaintainedTransactionPool<Block = Block, Hash = Block::Hash> + 'static,
+	Balance: Codec + MaybeDisplay + Sync,
+	Threshold: Clone + Into<u128> + Sync,
+{
+	type Block = Block;
+	type P = P;
+	type ExPool = ExPool;
+	type Balance = Balance;
+	type ThresholdParameter = Threshold;
+
+	fn should_place_order<'a>(ctx: &'a CriteriaContext<'a, Self>) -> DecisionFuture<'a> {
+		let decision = match threshold(ctx.settings, ThresholdKind::PendingWeight) {
+			Some(ref_time) => {
+				let weight = Self::pending_weight(ctx.parachain, ctx.transaction_pool.clone());
+				let reason = format!("pending ref time {} of {}", weight, ref_time);
+				reached(ReasonCode::PendingWeight, weight as u128, ref_time, reason)
+			},
+			None => Decision::skip(ReasonCode::PendingWeight, "pending weight threshold not set"),
+		};
+
+		Box::pin(future::ready(decision))
+	}
+
+	fn threshold_progress(decision: &Decision) -> Option<u32> {
+		decision.progress(ReasonCode::PendingWeight)
+	}
+}
+
+/// Met when the ready transactions would fill a block of `MaxLength` bytes up to the
+/// `BlockFullness` threshold of the runtime, as a percentage.
+pub struct BlockFullness<Block, P, ExPool, Balance, Threshold, MaxLength>(
+	PhantomData<(Block, P, ExPool, Balance, Threshold, MaxLength)>,
+);
+
+impl<Block, P, ExPool, Balance, Threshold, MaxLength>
+	BlockFullness<Block, P, ExPool, Balance, Threshold, MaxLength>
+where
+	Block: BlockT,
+	ExPool: MaintainedTransactionPool<Block = Block, Hash = Block::Hash> + 'static,
+	MaxLength: Get<u32>,
+{
+	/// Returns how full a block containing all the ready transactions would be, as a percentage.
+	pub fn fullness(transaction_pool: Arc<ExPool>) -> u32 {
+		let length = transaction_pool
+			.ready()
+			.fold(0u128, |total, tx| total.saturating_add(tx.data().encoded_size() as u128));
+		percentage(length, MaxLength::get().into())
+	}
+}
+
+impl<Block, P, ExPool, Balance, Threshold, MaxLength> OrderCriteria
+	for BlockFullness<Block, P, ExPool, Balance, Threshold, MaxLength>
+where
+	Block: BlockT,
+	P: ProvideRuntimeApi<Block> + UsageProvider<Block> + Send + Sync,
+	ExPool: MaintainedTransactionPool<Block = Block, Hash = Block::Hash> + 'static,
+	Balance: Sync,
+	Threshold: Clone + Into<u128> + Sync,
+	MaxLength: Get<u32>,
+{
+	type Block = Block;
+	type P = P;
+	type ExPool = ExPool;
+	type Balance = Balance;
+	type ThresholdParameter = Threshold;
+
+	fn should_place_order<'a>(ctx: &'a CriteriaContext<'a, Self>) -> DecisionFuture<'a> {
+		let decision = match threshold(ctx.settings, ThresholdKind::BlockFullness) {
+			Some(percent) => {
+				let fullness = Self::fullness(ctx.transaction_pool.clone());
+				let reason = format!("block {}% full of {}%", fullness, percent);
+				reached(ReasonCode::BlockFullness, fullness as u128, percent, reason)
+			},
+			None => Decision::skip(ReasonCode::BlockFullness, "block fullness threshold not set"),
+		};
+
+		Box::pin(future::ready(decision))
+	}
+
+	fn threshold_progress(decision: &Decision) -> Option<u32> {
+		decision.progress(ReasonCode::BlockFullness)
+	}
+}
+
+/// Met when the parachain head didn't change for the `IdleTime` threshold of the runtime, in
+/// relay chain blocks.
+pub struct TimeSinceLastBlock<Block, P, ExPool, Balance, Threshold>(
+	PhantomData<(Block, P, ExPool, Balance, Threshold)>,
+);
+
+impl<Block, P, ExPool, Balance, Threshold> OrderCriteria
+	for TimeSinceLastBlock<Block, P, ExPool, Balance, Threshold>
+where
+	Block: BlockT,
+	P: ProvideRuntimeApi<Block> + UsageProvider<Block> + Send + Sync,
+	ExPool: MaintainedTransactionPool<Block = Block, Hash = Block::Hash> + 'static,
+	Balance: Sync,
+	Threshold: Clone + Into<u128> + Sync,
+{
+	type Block = Block;
+	type P = P;
+	type ExPool = ExPool;
+	type Balance = Balance;
+	type ThresholdParameter = Threshold;
+
+	fn should_place_order<'a>(ctx: &'a CriteriaContext<'a, Self>) -> DecisionFuture<'a> {
+		let decision = match threshold(ctx.settings, ThresholdKind::IdleTime) {
+			Some(idle_time) => {
+				let idle = ctx.idle_relay_blocks as u128;
+				let reason = format!("{} of {} relay blocks since the last block", idle, idle_time);
+				reached(ReasonCode::IdleTime, idle, idle_time, reason)
+			},
+			None => Decision::skip(ReasonCode::IdleTime, "idle time threshold not set"),
+		};
+
+		Box::pin(future::ready(decision))
+	}
+
+	fn threshold_progress(decision: &Decision) -> Option<u32> {
+		decision.progress(ReasonCode::IdleTime)
+	}
+}
+
+/// Met when the spot price of an order is at most the `Price` threshold of the runtime.
+///
+/// Meant to be combined with other criteria, to only place orders while coretime is cheap.
+pub struct SpotPriceBelow<Block, P, ExPool, Balance, Threshold>(
+	PhantomData<(Block, P, ExPool, Balance, Threshold)>,
+);
+
+impl<Block, P, ExPool, Balance, Threshold> OrderCriteria
+	for SpotPriceBelow<Block, P, ExPool, Balance, Threshold>
+where
+	Block: BlockT,
+	P: ProvideRuntimeApi<Block> + UsageProvider<Block> + Send + Sync,
+	ExPool: MaintainedTransactionPool<Block = Block, Hash = Block::Hash> + 'static,
+	Balance: Sync,
+	Threshold: Clone + Into<u128> + Sync,
+{
+	type Block = Block;
+	type P = P;
+	type ExPool = ExPool;
+	type Balance = Balance;
+	type ThresholdParameter = Threshold;
+
+	fn should_place_order<'a>(ctx: &'a CriteriaContext<'a, Self>) -> DecisionFuture<'a> {
+		let decision = match threshold(ctx.settings, ThresholdKind::Price) {
+			Some(price) => {
+				let reason = format!("spot price {} of at most {}", ctx.spot_price, price);
+				let decision = if ctx.spot_price <= price {
+					Decision::place(ReasonCode::Price, reason)
+				} else {
+					Decision::skip(ReasonCode::Price, reason)
+				};
+				decision.measured(ReasonCode::Price, ctx.spot_price, price)
+			},
+			None => Decision::skip(ReasonCode::Price, "price threshold not set"),
+		};
+
+		Box::pin(future::ready(decision))
+	}
+}
+
+/// Met when the inbound messages queued for the parachain on the relay chain reach the message
+/// threshold of the runtime.
+///
+/// Both downward messages and HRMP messages from other parachains are taken into account.
+pub struct InboundMessages<Block, P, ExPool, Balance, Threshold>(
+	PhantomData<(Block, P, ExPool, Balance, Threshold)>,
+);
+
+impl<Block, P, ExPool, Balance, Threshold> OrderCriteria
+	for InboundMessages<Block, P, ExPool, Balance, Threshold>
+where
+	Block: BlockT,
+	P: ProvideRuntimeApi<Block> + UsageProvider<Block> + Send + Sync,
+	ExPool: MaintainedTransactionPool<Block = Block, Hash = Block::Hash> + 'static,
+	Balance: Sync,
+	Threshold: Sync,
+{
+	type Block = Block;
+	type P = P;
+	type ExPool = ExPool;
+	type Balance = Balance;
+	type ThresholdParameter = Threshold;
+
+	fn should_place_order<'a>(ctx: &'a CriteriaContext<'a, Self>) -> DecisionFuture<'a> {
+		let threshold = ctx.settings.inbound_message_threshold;
+
+		Box::pin(async move {
+			let Some(threshold) = threshold else {
+				return Decision::skip(
+					ReasonCode::InboundMessages,
+					"inbound message threshold not set",
+				)
+			};
+
+			let downward = ctx.relay_chain.retrieve_dmq_contents(ctx.para_id, ctx.relay_hash).await;
+			let hrmp = ctx
+				.relay_chain
+				.retrieve_all_inbound_hrmp_channel_contents(ctx.para_id, ctx.relay_hash)
+				.await;
+			let (downward, hrmp) = match (downward, hrmp) {
+				(Ok(downward), Ok(hrmp)) => (downward, hrmp),
+				(Err(e), _) | (_, Err(e)) =>
+					return Decision::skip(
+						ReasonCode::InboundMessages,
+						format!("failed to read inbound messages: {}", e),
+					),
+			};
+
+			let sent_at: Vec<RelayBlockNumber> = downward
+				.iter()
+				.map(|m| m.sent_at)
+				.chain(hrmp.values().flatten().map(|m| m.sent_at))
+				.collect();
+			let Some(oldest) = sent_at.iter().min().copied() else {
+				return Decision::skip(ReasonCode::InboundMessages, "no inbound messages").measured(
+					ReasonCode::InboundMessages,
+					0,
+					threshold.count.into(),
+				)
+			};
+			let count = sent_at.len() as u32;
+			let age = ctx.relay_height.saturating_sub(oldest);
+
+			let reason = format!(
+				"{} of {} inbound messages, oldest queued for {} of {} relay blocks",
+				count, threshold.count, age, threshold.max_age
+			);
+			let decision = if count >= threshold.count {
+				Decision::place(ReasonCode::InboundMessages, reason)
+			} else if age >= threshold.max_age {
+				Decision::place(ReasonCode::InboundMessageAge, reason)
+			} else {
+				Decision::skip(ReasonCode::InboundMessages, reason)
+			};
+			decision
+				.measured(ReasonCode::InboundMessages, count.into(), threshold.count.into())
+				.measured(ReasonCode::InboundMessageAge, age.into(), threshold.max_age.into())
+		})
+	}
+}
+
+/// Returns `value` as a percentage of `threshold`, capped at 100.
+pub(crate) fn percentage(value: u128, threshold: u128) -> u32 {
+	if threshold == 0 {
+		return 100
+	}
+
+	value.saturating_mul(100).saturating_div(threshold).min(100) as u32
+}
+
+/// Places an order once `value` reaches `threshold`.
+fn reached(code: ReasonCode, value: u128, threshold: u128, reason: String) -> Decision {
+	let decision = if value >= threshold {
+		Decision::place(code, reason)
+	} else {
+		Decision::skip(code, reason)
+	};
+	decision.measured(code, value, threshold)
+}
+
+/// Returns the threshold of the given kind from the runtime settings, if set.
+fn threshold<Balance, Threshold>(
+	settings: &Settings<Balance, Threshold>,
+	kind: ThresholdKind,
+) -> Option<u128>
+where
+	Threshold: Clone + Into<u128>,
+{
+	settings.threshold(kind).cloned().map(Into::into)
+}
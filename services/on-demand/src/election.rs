@@ -0,0 +1,66 @@
+//! Election of the collator responsible for placing an order.
+
+use crate::RelayBlockNumber;
+use codec::Decode;
+use sp_consensus_aura::{Slot, AURA_ENGINE_ID};
+use sp_runtime::traits::Header as HeaderT;
+
+/// Elects the order placer among the authorities of the parachain.
+///
+/// All collators must reach the same result from the same inputs, so the election has to be
+/// deterministic.
+pub trait PlacerElection<AuthorPub> {
+	/// Elects the order placer among `authorities` based on the parent parachain header and the
+	/// relay chain block number.
+	fn elect<Header: HeaderT>(
+		authorities: &[AuthorPub],
+		para_header: &Header,
+		relay_height: RelayBlockNumber,
+	) -> Option<AuthorPub>;
+}
+
+/// Elects the author of the parent parachain block, as found in its Aura pre-runtime digest.
+pub struct AuraElection;
+
+impl<AuthorPub: Clone> PlacerElection<AuthorPub> for AuraElection {
+	fn elect<Header: HeaderT>(
+		authorities: &[AuthorPub],
+		para_header: &Header,
+		_relay_height: RelayBlockNumber,
+	) -> Option<AuthorPub> {
+		if authorities.is_empty() {
+			return None
+		}
+
+		let slot = aura_slot(para_header)?;
+		authorities.get((*slot % authorities.len() as u64) as usize).cloned()
+	}
+}
+
+/// Rotates through the authorities, moving on to the next one every relay chain block.
+pub struct RoundRobin;
+
+impl<AuthorPub: Clone> PlacerElection<AuthorPub> for RoundRobin {
+	fn elect<Header: HeaderT>(
+		authorities: &[AuthorPub],
+		_para_header: &Header,
+		relay_height: RelayBlockNumber,
+	) -> Option<AuthorPub> {
+		if authorities.is_empty() {
+			return None
+		}
+
+		authorities.get(relay_height as usize % authorities.len()).cloned()
+	}
+}
+
+/// Returns the Aura slot in which the block with the given header was authored.
+pub(crate) fn aura_slot<Header: HeaderT>(header: &Header) -> Option<Slot> {
+	for (id, mut data) in header.digest().logs().iter().filter_map(|d| d.as_pre_runtime()) {
+		if id == AURA_ENGINE_ID {
+			return Slot::decode(&mut data).ok()
+		}
+	}
+
+	None
+}
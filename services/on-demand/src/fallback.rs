@@ -0,0 +1,70 @@
+//! Fallback schedule for when the elected order placer doesn't place an order.
+//!
+//! If the elected collator is offline, nobody places an order and the parachain can't progress.
+//! Every `rotate_after` relay chain blocks without the parachain head changing, responsibility
+//! for a due order moves to the next authority in the set. After every authority had its turn,
+//! any authority can place the order.
+//!
+//! The age of the parachain head is observed on the relay chain, which all collators follow, so
+//! they agree on who is eligible at any point without having to coordinate. Whether an order is
+//! due depends on the local transaction pool, so it doesn't affect the rotation.
+
+use cumulus_primitives_core::relay_chain::BlockNumber as RelayBlockNumber;
+use sp_core::H256;
+use std::sync::Mutex;
+
+/// Configures when the order placement responsibility is passed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlacerFallback {
+	/// The number of relay chain blocks after which the next authority becomes responsible.
+	pub rotate_after: RelayBlockNumber,
+}
+
+/// The authorities which are allowed to place an order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EligiblePlacer<AuthorPub> {
+	/// Only the given authority.
+	One(AuthorPub),
+	/// Any of the authorities.
+	Any,
+}
+
+impl PlacerFallback {
+	/// Returns who is eligible to place an order while the parachain head hasn't changed for
+	/// `idle` relay chain blocks.
+	pub fn eligible<AuthorPub: Clone + PartialEq>(
+		&self,
+		expected: AuthorPub,
+		authorities: &[AuthorPub],
+		idle: RelayBlockNumber,
+	) -> EligiblePlacer<AuthorPub> {
+		let step = (idle / self.rotate_after.max(1)) as usize;
+		if step == 0 || authorities.is_empty() {
+			return EligiblePlacer::One(expected)
+		}
+		if step >= authorities.len() {
+			return EligiblePlacer::Any
+		}
+
+		let start = authorities.iter().position(|a| *a == expected).unwrap_or_default();
+		EligiblePlacer::One(authorities[(start + step) % authorities.len()].clone())
+	}
+}
+
+/// Keeps track of how long the parachain head has remained unchanged.
+#[derive(Default)]
+pub struct HeadTracker(Mutex<Option<(H256, RelayBlockNumber)>>);
+
+impl HeadTracker {
+	/// Returns for how many relay chain blocks the parachain head has been `para_head` at `now`.
+	pub fn idle_for(&self, para_head: H256, now: RelayBlockNumber) -> RelayBlockNumber {
+		let mut last = self.0.lock().expect("Poisoned lock");
+		match *last {
+			Some((head, since)) if head == para_head => now.saturating_sub(since),
+			_ => {
+				*last = Some((para_head, now));
+				0
+			},
+		}
+	}
+}
@@ -3,38 +3,83 @@
 //! NOTE: Inspiration was taken from the Magnet(https://github.com/Magport/Magnet) on-demand integration.
 
 use crate::{
-	chain::{
-		get_spot_price, is_parathread, on_demand_cores_available,
-		polkadot::runtime_types::{frame_system::AccountInfo, pallet_balances::types::AccountData},
-	},
-	config::{OnDemandConfig, OrderCriteria},
+	budget::{SpendBudget, SpendTracker},
+	chain::{get_pricing_state, is_parathread, on_demand_capacity},
+	config::{Decision, OnDemandConfig, OrderContext, OrderCriteria, ReasonCode},
+	fallback::{EligiblePlacer, HeadTracker, PlacerFallback},
+	metadata::{MetadataCache, RelayMetadata},
+	metrics::{reason, Metrics},
+	order_record::OrderRecords,
+	payment::{PaymentAccounts, PaymentSigner, KEY_TYPE as PAYMENT_KEY_TYPE},
+	settings::SettingsCache,
 };
-use codec::Decode;
+use codec::{Codec, Decode};
 use cumulus_primitives_core::{
-	relay_chain::{BlockNumber as RelayBlockNumber, Nonce},
-	ParaId, PersistedValidationData,
+	relay_chain::BlockNumber as RelayBlockNumber, ParaId, PersistedValidationData,
 };
 use cumulus_relay_chain_interface::{RelayChainInterface, RelayChainResult};
-use futures::{pin_mut, select, FutureExt, Stream, StreamExt};
+use futures::{pin_mut, Stream, StreamExt};
+use futures_timer::Delay;
 use on_demand_primitives::{
-	well_known_keys::{account, ON_DEMAND_QUEUE},
-	EnqueuedOrder,
+	well_known_keys::ON_DEMAND_QUEUE, EnqueuedOrder, OnDemandRuntimeApi, OnDemandSettings,
+	ThresholdParameterT,
 };
 use polkadot_primitives::OccupiedCoreAssumption;
-use sc_service::TaskManager;
-use sp_core::H256;
-use sp_keystore::KeystorePtr;
+use prometheus_endpoint::Registry;
+use sc_client_api::UsageProvider;
+use sc_service::{SpawnTaskHandle, TaskManager};
+use sc_transaction_pool_api::{TransactionPool, TransactionSource};
+use sp_api::{ApiExt, Core, Metadata as MetadataApi, ProvideRuntimeApi};
+use sp_core::{sr25519, ByteArray, H256};
+use sp_keystore::{KeystoreExt, KeystorePtr};
 use sp_runtime::{
-	traits::{Block as BlockT, Header},
+	traits::{Block as BlockT, Header, MaybeDisplay},
 	RuntimeAppPublic,
 };
-use std::{error::Error, net::SocketAddr, sync::Arc, time::Duration};
+use std::{
+	error::Error,
+	net::SocketAddr,
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+use subxt::{OnlineClient, PolkadotConfig};
 
+pub mod budget;
 mod chain;
 pub mod config;
+pub mod criteria;
+pub mod election;
+pub mod fallback;
+pub mod metadata;
+pub mod metrics;
+pub mod order_record;
+pub mod payment;
+pub mod pricing;
+pub mod settings;
+#[cfg(test)]
+mod tests;
 
 const LOG_TARGET: &str = "on-demand-service";
 
+/// The number of relay chain blocks ahead for which the spot price is estimated.
+///
+/// An order only gets processed in a later relay chain block, by which point the spot price may
+/// have increased.
+const PRICE_LOOKAHEAD: u32 = 2;
+
+/// Time to wait before restarting a stopped on-demand task.
+const RESTART_DELAY: Duration = Duration::from_secs(6);
+
+/// Names of the on-demand tasks, used in logs and as metric labels.
+mod task {
+	pub const ORDER_PLACEMENT: &str = "order_placement";
+	pub const EVENT_NOTIFICATION: &str = "event_notification";
+	pub const SETTINGS: &str = "settings";
+}
+
+type RuntimeApiOf<Config> =
+	<<Config as OnDemandConfig>::P as ProvideRuntimeApi<<Config as OnDemandConfig>::Block>>::Api;
+
 /// Start all the on-demand order creation related tasks.
 pub fn start_on_demand<Config>(
 	parachain: Arc<Config::P>,
@@ -45,12 +90,28 @@ pub fn start_on_demand<Config>(
 	keystore: KeystorePtr,
 	relay_rpc: Option<SocketAddr>,
 	rc_balance_baseline: Config::Balance,
+	allow_death: bool,
 	rc_slot_duration: Duration,
+	spend_budget: Option<SpendBudget>,
+	placer_fallback: Option<PlacerFallback>,
+	prometheus_registry: Option<&Registry>,
 ) -> sc_service::error::Result<()>
 where
 	Config: OnDemandConfig + 'static,
-	Config::OrderPlacementCriteria:
-		OrderCriteria<P = Config::P, Block = Config::Block, ExPool = Config::ExPool>,
+	Config::OrderPlacementCriteria: OrderCriteria<
+		P = Config::P,
+		Block = Config::Block,
+		ExPool = Config::ExPool,
+		Balance = Config::Balance,
+		ThresholdParameter = Config::ThresholdParameter,
+	>,
+	RuntimeApiOf<Config>: OnDemandRuntimeApi<
+			Config::Block,
+			Config::Balance,
+			RelayBlockNumber,
+			Config::ThresholdParameter,
+		> + Core<Config::Block>
+		+ MetadataApi<Config::Block>,
 {
 	let mut url = String::from("ws://"); // <- TODO wss
 	url.push_str(
@@ -59,20 +120,32 @@ where
 			.to_string(),
 	);
 
+	let metrics = prometheus_registry
+		.map(Metrics::register)
+		.transpose()
+		.map_err(|e| sc_service::Error::Application(Box::new(e)))?;
+
 	let on_demand_task = run_on_demand_task::<Config>(
 		para_id,
 		parachain,
 		relay_chain,
 		keystore,
+		task_manager.spawn_handle(),
 		transaction_pool,
 		url,
 		rc_balance_baseline,
+		allow_death,
 		rc_slot_duration,
+		spend_budget,
+		placer_fallback,
+		metrics,
 	);
 
-	task_manager.spawn_essential_handle().spawn_blocking(
+	// Order placement isn't required for the node to function, so failures don't bring it down.
+	// The task is dropped when the node shuts down.
+	task_manager.spawn_handle().spawn(
 		"on-demand order placement task",
-		"on-demand",
+		Some("on-demand"),
 		on_demand_task,
 	);
 
@@ -84,97 +157,240 @@ async fn run_on_demand_task<Config>(
 	parachain: Arc<Config::P>,
 	relay_chain: Config::R,
 	keystore: KeystorePtr,
+	spawner: SpawnTaskHandle,
 	transaction_pool: Arc<Config::ExPool>,
 	relay_url: String,
 	rc_balance_baseline: Config::Balance,
+	allow_death: bool,
 	rc_slot_duration: Duration,
+	spend_budget: Option<SpendBudget>,
+	placer_fallback: Option<PlacerFallback>,
+	metrics: Option<Metrics>,
 ) where
 	Config: OnDemandConfig + 'static,
-	Config::OrderPlacementCriteria:
-		OrderCriteria<P = Config::P, Block = Config::Block, ExPool = Config::ExPool>,
+	Config::OrderPlacementCriteria: OrderCriteria<
+		P = Config::P,
+		Block = Config::Block,
+		ExPool = Config::ExPool,
+		Balance = Config::Balance,
+		ThresholdParameter = Config::ThresholdParameter,
+	>,
+	RuntimeApiOf<Config>: OnDemandRuntimeApi<
+			Config::Block,
+			Config::Balance,
+			RelayBlockNumber,
+			Config::ThresholdParameter,
+		> + Core<Config::Block>
+		+ MetadataApi<Config::Block>,
 {
 	log::info!(
 		target: LOG_TARGET,
 		"Starting on-demand task"
 	);
 
-	let relay_chain_notification = follow_relay_chain::<Config>(
-		para_id,
-		parachain,
-		relay_chain,
-		keystore,
-		transaction_pool,
-		relay_url,
-		rc_balance_baseline,
-		rc_slot_duration,
-	);
+	let order_records = OrderRecords::default();
+	let spend_tracker = SpendTracker::new(spend_budget);
+	let settings_cache = SettingsCache::default();
+
+	let order_placement = async {
+		loop {
+			let result = follow_relay_chain::<Config>(
+				para_id,
+				&*parachain,
+				&relay_chain,
+				&keystore,
+				&spawner,
+				&transaction_pool,
+				&relay_url,
+				rc_balance_baseline,
+				allow_death,
+				rc_slot_duration,
+				&order_records,
+				&spend_tracker,
+				&settings_cache,
+				placer_fallback,
+				metrics.as_ref(),
+			)
+			.await;
+			restart_after(task::ORDER_PLACEMENT, result, metrics.as_ref()).await;
+		}
+	};
+
+	// Order placement doesn't depend on the event notifications, so they are restarted
+	// independently.
+	let event_notifications = async {
+		loop {
+			let result = event_notification(para_id, &relay_url, &order_records).await;
+			restart_after(task::EVENT_NOTIFICATION, result, metrics.as_ref()).await;
+		}
+	};
+
+	// Until the settings are cached, order placement queries them from the runtime.
+	let settings_updates = async {
+		loop {
+			let result = settings::follow_parachain::<
+				Config::Block,
+				Config::P,
+				Config::Backend,
+				Config::Balance,
+				Config::ThresholdParameter,
+			>(&*parachain, &settings_cache)
+			.await;
+			restart_after(task::SETTINGS, result, metrics.as_ref()).await;
+		}
+	};
+
+	futures::future::join3(order_placement, event_notifications, settings_updates).await;
+}
 
-	// let event_notification = event_notification(para_id, url, order_record);
-	select! {
-		_ = relay_chain_notification.fuse() => {},
-		// _ = event_notification.fuse() => {},
+/// Logs the outcome of a stopped task and waits before it gets restarted.
+async fn restart_after(task: &str, result: Result<(), Box<dyn Error>>, metrics: Option<&Metrics>) {
+	match result {
+		Ok(()) => log::warn!(
+			target: LOG_TARGET,
+			"On-demand {} task stopped, restarting in {:?}",
+			task,
+			RESTART_DELAY
+		),
+		Err(e) => log::error!(
+			target: LOG_TARGET,
+			"On-demand {} task failed: {}, restarting in {:?}",
+			task,
+			e,
+			RESTART_DELAY
+		),
 	}
+
+	if let Some(metrics) = metrics {
+		metrics.task_restarted(task);
+	}
+
+	Delay::new(RESTART_DELAY).await;
+}
+
+/// Follows the relay chain events to find out whether our orders resulted in a backed candidate.
+async fn event_notification(
+	para_id: ParaId,
+	relay_url: &str,
+	order_records: &OrderRecords,
+) -> Result<(), Box<dyn Error>> {
+	let client = OnlineClient::<PolkadotConfig>::from_url(relay_url).await?;
+	let mut blocks = client.blocks().subscribe_best().await?;
+
+	while let Some(block) = blocks.next().await {
+		let block = block?;
+		let events = block.events().await?;
+
+		for event in events.iter().filter_map(|event| event.ok()) {
+			let Some(core) = chain::backed_core(&event, para_id) else { continue };
+
+			if let Some(record) = order_records.backed(block.hash(), block.number(), core) {
+				log::info!(
+					target: LOG_TARGET,
+					"Order {:?} resulted in a candidate backed on core {} in relay block {:?}",
+					record.tx_hash,
+					core,
+					block.hash(),
+				);
+			}
+		}
+	}
+
+	Ok(())
 }
 
+/// Tries to place an order on every new best relay chain block.
+///
+/// Returns once the relay chain stops producing notifications.
 async fn follow_relay_chain<Config>(
 	para_id: ParaId,
-	parachain: Arc<Config::P>,
-	relay_chain: Config::R,
-	keystore: KeystorePtr,
-	transaction_pool: Arc<Config::ExPool>,
-	relay_url: String,
+	parachain: &Config::P,
+	relay_chain: &Config::R,
+	keystore: &KeystorePtr,
+	spawner: &SpawnTaskHandle,
+	transaction_pool: &Arc<Config::ExPool>,
+	relay_url: &str,
 	rc_balance_baseline: Config::Balance,
+	allow_death: bool,
 	rc_slot_duration: Duration,
-) where
+	order_records: &OrderRecords,
+	spend_tracker: &SpendTracker,
+	settings_cache: &SettingsCache<Config::Balance, Config::ThresholdParameter>,
+	placer_fallback: Option<PlacerFallback>,
+	metrics: Option<&Metrics>,
+) -> Result<(), Box<dyn Error>>
+where
 	Config: OnDemandConfig + 'static,
-	Config::OrderPlacementCriteria:
-		OrderCriteria<P = Config::P, Block = Config::Block, ExPool = Config::ExPool>,
+	Config::OrderPlacementCriteria: OrderCriteria<
+		P = Config::P,
+		Block = Config::Block,
+		ExPool = Config::ExPool,
+		Balance = Config::Balance,
+		ThresholdParameter = Config::ThresholdParameter,
+	>,
+	RuntimeApiOf<Config>: OnDemandRuntimeApi<
+		Config::Block,
+		Config::Balance,
+		RelayBlockNumber,
+		Config::ThresholdParameter,
+	>,
 {
-	let new_best_heads = match new_best_heads(relay_chain.clone(), para_id).await {
-		Ok(best_heads_stream) => best_heads_stream.fuse(),
-		Err(_err) => {
+	let new_best_heads = new_best_heads(relay_chain.clone(), para_id).await?;
+
+	let metadata_cache = MetadataCache::default();
+	let head_tracker = HeadTracker::default();
+	let last_price_report = Mutex::default();
+
+	pin_mut!(new_best_heads);
+	while let Some((height, validation_data, r_hash)) = new_best_heads.next().await {
+		log::info!(
+			target: LOG_TARGET,
+			"New best relay head: {}",
+			r_hash,
+		);
+
+		if let Some(metrics) = metrics {
+			metrics.refresh_since_last_order();
+		}
+
+		if let Err(e) = handle_relaychain_stream::<Config>(
+			validation_data,
+			height,
+			parachain,
+			keystore.clone(),
+			spawner,
+			transaction_pool.clone(),
+			relay_chain.clone(),
+			&metadata_cache,
+			r_hash,
+			para_id,
+			relay_url.to_string(),
+			rc_balance_baseline,
+			allow_death,
+			rc_slot_duration,
+			order_records,
+			spend_tracker,
+			settings_cache,
+			placer_fallback,
+			&head_tracker,
+			&last_price_report,
+			metrics,
+		)
+		.await
+		{
 			log::error!(
 				target: LOG_TARGET,
-				"Error: {:?}",
-				_err
+				"Failed to handle relay chain block {}: {}",
+				r_hash,
+				e
 			);
-			return;
-		},
-	};
-
-	pin_mut!(new_best_heads);
-	loop {
-		select! {
-			h = new_best_heads.next() => {
-				match h {
-					Some((height, validation_data, r_hash)) => {
-						log::info!(
-							target: LOG_TARGET,
-							"New best relay head: {}",
-							r_hash,
-						);
-
-						let _ = handle_relaychain_stream::<Config>(
-							validation_data,
-							height,
-							&*parachain,
-							keystore.clone(),
-							transaction_pool.clone(),
-							relay_chain.clone(),
-							r_hash,
-							para_id,
-							relay_url.clone(),
-							rc_balance_baseline,
-							rc_slot_duration,
-						).await;
-					},
-					None => {
-						return;
-					}
-				}
-			},
+			if let Some(metrics) = metrics {
+				metrics.error();
+			}
 		}
 	}
+
+	Ok(())
 }
 
 /// Order placement logic
@@ -183,19 +399,43 @@ async fn handle_relaychain_stream<Config>(
 	relay_height: RelayBlockNumber,
 	parachain: &Config::P,
 	keystore: KeystorePtr,
+	spawner: &SpawnTaskHandle,
 	transaction_pool: Arc<Config::ExPool>,
 	relay_chain: Config::R,
+	metadata_cache: &MetadataCache,
 	r_hash: H256,
 	para_id: ParaId,
 	relay_url: String,
 	rc_balance_baseline: Config::Balance,
+	allow_death: bool,
 	rc_slot_duration: Duration,
+	order_records: &OrderRecords,
+	spend_tracker: &SpendTracker,
+	settings_cache: &SettingsCache<Config::Balance, Config::ThresholdParameter>,
+	placer_fallback: Option<PlacerFallback>,
+	head_tracker: &HeadTracker,
+	last_price_report: &Mutex<Option<<Config::Block as BlockT>::Hash>>,
+	metrics: Option<&Metrics>,
 ) -> Result<(), Box<dyn Error>>
 where
 	Config: OnDemandConfig + 'static,
-	Config::OrderPlacementCriteria:
-		OrderCriteria<P = Config::P, Block = Config::Block, ExPool = Config::ExPool>,
+	Config::OrderPlacementCriteria: OrderCriteria<
+		P = Config::P,
+		Block = Config::Block,
+		ExPool = Config::ExPool,
+		Balance = Config::Balance,
+		ThresholdParameter = Config::ThresholdParameter,
+	>,
+	RuntimeApiOf<Config>: OnDemandRuntimeApi<
+		Config::Block,
+		Config::Balance,
+		RelayBlockNumber,
+		Config::ThresholdParameter,
+	>,
 {
+	// Track the parachain head on every relay chain block, so that its age is accurate.
+	let idle_relay_blocks = head_tracker.idle_for(validation_data.parent_head.hash(), relay_height);
+
 	let is_parathread = is_parathread(&relay_chain, r_hash, para_id).await?;
 
 	if !is_parathread {
@@ -207,11 +447,12 @@ where
 		return Ok(())
 	}
 
-	let available = on_demand_cores_available(&relay_chain, r_hash)
-		.await
-		.ok_or("Failed to check if there are on-demand cores available")?;
+	// Ensure the relay chain layout is compatible before interacting with the on-demand pallet.
+	let metadata = metadata_cache.at(&relay_chain, r_hash).await?;
+
+	let capacity = on_demand_capacity(&relay_chain, &metadata, r_hash).await?;
 
-	if available {
+	if !capacity.is_available() {
 		log::info!(
 			target: LOG_TARGET,
 			"No cores allocated to on-demand"
@@ -220,45 +461,92 @@ where
 		return Ok(())
 	}
 
-	for acc in keystore.keys(sp_application_crypto::key_types::AURA)?.iter() {
+	log::debug!(
+		target: LOG_TARGET,
+		"On-demand capacity: {} parts of 57600 across {:?}",
+		capacity.total_parts(),
+		capacity.cores,
+	);
+
+	let baseline: u128 = rc_balance_baseline.into();
+	for acc in keystore.sr25519_public_keys(PAYMENT_KEY_TYPE) {
 		// Check if any of the accounts is below the baseline balance.
-		let rc_account_storage = relay_chain.get_storage_by_key(r_hash, &account(acc)).await?;
-		if let Some(rc_account_storage) = rc_account_storage {
-			let rc_account: AccountInfo<Nonce, AccountData<Config::Balance>> =
-				AccountInfo::decode(&mut &rc_account_storage[..])?;
+		let free = chain::free_balance(&relay_chain, &metadata, r_hash, acc.as_ref()).await?;
 
-			if rc_account.data.free <= rc_balance_baseline {
-				log::warn!(
-					target: LOG_TARGET,
-					"Low relay chain balance: {}",
-					rc_account.data.free
-				)
-			}
+		if let Some(metrics) = metrics {
+			metrics.relay_balance(&acc.to_string(), free);
+		}
+
+		if free <= baseline {
+			log::warn!(
+				target: LOG_TARGET,
+				"Low relay chain balance: {}",
+				free
+			)
 		}
 	}
 
-	let head_encoded = validation_data.clone().parent_head.0;
-	let para_head = <<Config::Block as BlockT>::Header>::decode(&mut &head_encoded[..])?;
+	let Some(pricing) = get_pricing_state(&relay_chain, &metadata, r_hash).await else {
+		if let Some(metrics) = metrics {
+			metrics.order_failed(reason::SPOT_PRICE);
+		}
+		return Err("Failed to get spot price".into())
+	};
+
+	if let Some(metrics) = metrics {
+		metrics.spot_price(pricing.spot_price());
+	}
 
-	let order_placer = Config::order_placer(
-		Box::leak(Box::new(relay_chain.clone())), // <- TODO
+	if let Err(e) = report_spot_price::<Config>(
 		parachain,
-		r_hash,
-		para_head,
-		rc_slot_duration,
+		&keystore,
+		&transaction_pool,
+		Config::Balance::from(pricing.spot_price()),
+		last_price_report,
 	)
-	.await?
-	.clone();
+	.await
+	{
+		log::warn!(target: LOG_TARGET, "Failed to report the spot price: {}", e);
+	}
 
-	if !keystore.has_keys(&[(order_placer.to_raw_vec(), sp_application_crypto::key_types::AURA)]) {
-		// Expected author is not in the keystore therefore we are not responsible for order
-		// creation.
-		log::info!(
-			target: LOG_TARGET,
-			"Waiting for {} to create an order",
-			order_placer
-		);
-		return Ok(())
+	let spot_price = match pricing.predict_spot_price(PRICE_LOOKAHEAD) {
+		Ok(predicted) => predicted,
+		Err(err) => {
+			log::warn!(target: LOG_TARGET, "Failed to predict the spot price: {}", err);
+			pricing.spot_price()
+		},
+	};
+	let spot_price = Config::Balance::from(spot_price);
+
+	let settings = match settings_cache.get() {
+		Some(settings) => settings,
+		None => on_demand_settings::<
+			Config::Block,
+			Config::P,
+			Config::Balance,
+			Config::ThresholdParameter,
+		>(parachain, parachain.usage_info().chain.best_hash)?,
+	};
+
+	let ctx = OrderContext {
+		parachain,
+		transaction_pool: transaction_pool.clone(),
+		relay_chain: &relay_chain,
+		para_id,
+		relay_hash: r_hash,
+		relay_height,
+		validation_data: &validation_data,
+		spot_price: spot_price.into(),
+		idle_relay_blocks,
+		settings: &settings,
+	};
+
+	// Before placing an order ensure that the criteria for placing an order has been reached.
+	let mut decision = Config::OrderPlacementCriteria::should_place_order(&ctx).await;
+	if let Some(metrics) = metrics {
+		if let Some(progress) = Config::OrderPlacementCriteria::threshold_progress(&decision) {
+			metrics.threshold_progress(progress);
+		}
 	}
 
 	let on_demand_queue_storage = relay_chain.get_storage_by_key(r_hash, ON_DEMAND_QUEUE).await?;
@@ -272,35 +560,408 @@ where
 		false
 	};
 
-	if order_exists {
+	// The previous order may still be on its way into the queue.
+	if order_exists || order_records.awaiting_inclusion(relay_height) {
 		return Ok(())
 	}
 
-	// Before placing an order ensure that the criteria for placing an order has been reached.
-	let order_criteria_reached = Config::OrderPlacementCriteria::should_place_order(
+	// Keep the parachain alive, even if the criteria isn't met for a long time.
+	if let Some(max_idle) = settings.max_idle_relay_blocks {
+		if !decision.place_order && idle_relay_blocks >= max_idle {
+			decision = Decision {
+				place_order: true,
+				code: ReasonCode::KeepAlive,
+				reason: format!(
+					"no parachain block for {} of {} relay blocks",
+					idle_relay_blocks, max_idle
+				),
+				..decision
+			}
+			.measured(ReasonCode::KeepAlive, idle_relay_blocks.into(), max_idle.into());
+		}
+	}
+
+	log::info!(
+		target: LOG_TARGET,
+		"Order placement criteria {}: {}",
+		if decision.place_order { "met" } else { "not met" },
+		decision.reason,
+	);
+	if let Some(metrics) = metrics {
+		metrics.criteria_decision(&decision);
+	}
+
+	if !decision.place_order {
+		return Ok(())
+	}
+
+	// Nobody places an order above the maximum price, so there is no placer to elect.
+	if let Some(max_price) = settings.max_order_price {
+		if spot_price > max_price {
+			log::warn!(
+				target: LOG_TARGET,
+				"Spot price {} exceeds the maximum order price {}, skipping order",
+				spot_price,
+				max_price
+			);
+			if let Some(metrics) = metrics {
+				metrics.order_failed(reason::PRICE_CAP);
+			}
+			return Ok(())
+		}
+	}
+
+	let head_encoded = validation_data.clone().parent_head.0;
+	let para_head = <<Config::Block as BlockT>::Header>::decode(&mut &head_encoded[..])?;
+	let authorities = Config::authorities(parachain, para_head.hash())?;
+	let payment_accounts = authority_payment_accounts::<
+		Config::Block,
+		Config::P,
+		Config::Balance,
+		Config::ThresholdParameter,
+	>(parachain, para_head.hash(), &authorities)?;
+
+	let expected_placer = Config::order_placer(
+		relay_chain.clone(),
 		parachain,
-		transaction_pool,
+		r_hash,
 		relay_height,
-	);
+		para_head,
+		rc_slot_duration,
+	)
+	.await?;
+
+	// If the elected order placer didn't place the order in time, responsibility is passed on.
+	let eligible = match placer_fallback {
+		Some(fallback) => fallback.eligible(expected_placer, &authorities, idle_relay_blocks),
+		None => EligiblePlacer::One(expected_placer),
+	};
 
-	if !order_criteria_reached {
+	let order_placer = match eligible {
+		EligiblePlacer::One(expected_placer) => {
+			// If the expected order placer can't afford the order, responsibility is passed on
+			// to the next authority that can.
+			let Some(order_placer) = paying_order_placer::<Config>(
+				&relay_chain,
+				&metadata,
+				r_hash,
+				&payment_accounts,
+				expected_placer,
+				authorities,
+				spot_price.into(),
+				baseline,
+			)
+			.await?
+			else {
+				log::warn!(
+					target: LOG_TARGET,
+					"None of the authorities can afford an order for {} without dropping below the baseline balance",
+					spot_price
+				);
+				return Ok(())
+			};
+
+			if !keystore.has_keys(&[(
+				order_placer.to_raw_vec(),
+				<Config::AuthorPub as RuntimeAppPublic>::ID,
+			)]) {
+				// Expected author is not in the keystore therefore we are not responsible for
+				// order creation.
+				log::info!(
+					target: LOG_TARGET,
+					"Waiting for {} to create an order",
+					order_placer
+				);
+				return Ok(())
+			}
+
+			order_placer
+		},
+		EligiblePlacer::Any => {
+			let Some(order_placer) = local_paying_placer::<Config>(
+				&relay_chain,
+				&metadata,
+				r_hash,
+				&keystore,
+				&payment_accounts,
+				authorities,
+				spot_price.into(),
+				baseline,
+			)
+			.await?
+			else {
+				log::warn!(
+					target: LOG_TARGET,
+					"Parachain idle for {} relay blocks, but none of our authorities can afford an order",
+					idle_relay_blocks
+				);
+				return Ok(())
+			};
+
+			log::info!(
+				target: LOG_TARGET,
+				"Parachain idle for {} relay blocks, placing the order with {}",
+				idle_relay_blocks,
+				order_placer
+			);
+
+			order_placer
+		},
+	};
+
+	if !spend_tracker.can_spend(relay_height, spot_price.into()) {
+		if let Some(budget) = spend_tracker.budget() {
+			log::warn!(
+				target: LOG_TARGET,
+				"Spent {} in the last {} relay blocks, placing an order for {} would exceed the budget of {}, skipping order",
+				spend_tracker.spent(relay_height),
+				budget.window,
+				spot_price,
+				budget.max_spend
+			);
+		}
+		if let Some(metrics) = metrics {
+			metrics.order_failed(reason::BUDGET);
+		}
 		return Ok(())
 	}
 
-	let spot_price = get_spot_price::<Config::Balance>(relay_chain, r_hash)
-		.await
-		.ok_or("Failed to get spot price")?;
+	let signer =
+		PaymentSigner::for_authority(keystore, &payment_accounts, &order_placer.to_raw_vec())?;
 
 	log::info!(
 		target: LOG_TARGET,
-		"Placing an order",
+		"Placing an order as {}, paid by {}",
+		order_placer,
+		signer.public()
 	);
+	if let Some(metrics) = metrics {
+		metrics.order_attempted();
+	}
 
-	chain::submit_order(&relay_url, para_id, spot_price.into(), keystore).await?;
+	chain::submit_order(
+		&relay_url,
+		para_id,
+		spot_price.into(),
+		&signer,
+		allow_death,
+		relay_height,
+		capacity.cores.iter().map(|core| core.core.0).collect(),
+		order_records,
+		spend_tracker,
+		spawner,
+		metrics,
+	)
+	.await?;
 
 	Ok(())
 }
 
+/// Returns the first authority, starting from `expected`, whose payment account can pay `price`
+/// without its relay chain balance dropping below `baseline`.
+async fn paying_order_placer<Config>(
+	relay_chain: &Config::R,
+	metadata: &RelayMetadata,
+	r_hash: H256,
+	payment_accounts: &PaymentAccounts,
+	expected: Config::AuthorPub,
+	authorities: Vec<Config::AuthorPub>,
+	price: u128,
+	baseline: u128,
+) -> Result<Option<Config::AuthorPub>, Box<dyn Error>>
+where
+	Config: OnDemandConfig,
+{
+	let start = authorities.iter().position(|a| *a == expected).unwrap_or_default();
+
+	for offset in 0..authorities.len() {
+		let candidate = &authorities[(start + offset) % authorities.len()];
+		let Some(payer) = payment_accounts.payer(&candidate.to_raw_vec()) else { continue };
+		let free = chain::free_balance(relay_chain, metadata, r_hash, payer.as_ref()).await?;
+
+		if can_afford(free, price, baseline) {
+			if *candidate != expected {
+				log::info!(
+					target: LOG_TARGET,
+					"{} can't afford the order, falling back to {}",
+					expected,
+					candidate
+				);
+			}
+			return Ok(Some(candidate.clone()))
+		}
+	}
+
+	Ok(None)
+}
+
+/// Returns the first authority in our keystore whose payment account can pay `price` without its
+/// relay chain balance dropping below `baseline`.
+async fn local_paying_placer<Config>(
+	relay_chain: &Config::R,
+	metadata: &RelayMetadata,
+	r_hash: H256,
+	keystore: &KeystorePtr,
+	payment_accounts: &PaymentAccounts,
+	authorities: Vec<Config::AuthorPub>,
+	price: u128,
+	baseline: u128,
+) -> Result<Option<Config::AuthorPub>, Box<dyn Error>>
+where
+	Config: OnDemandConfig,
+{
+	for candidate in authorities {
+		if !keystore
+			.has_keys(&[(candidate.to_raw_vec(), <Config::AuthorPub as RuntimeAppPublic>::ID)])
+		{
+			continue
+		}
+		let Some(payer) = payment_accounts.payer(&candidate.to_raw_vec()) else { continue };
+		if !keystore.has_keys(&[(payer.to_raw_vec(), PAYMENT_KEY_TYPE)]) {
+			continue
+		}
+
+		let free = chain::free_balance(relay_chain, metadata, r_hash, payer.as_ref()).await?;
+		if can_afford(free, price, baseline) {
+			return Ok(Some(candidate))
+		}
+	}
+
+	Ok(None)
+}
+
+/// Returns whether paying `price` keeps the `free` balance at or above the `baseline`.
+fn can_afford(free: u128, price: u128, baseline: u128) -> bool {
+	free.checked_sub(price).map_or(false, |remaining| remaining >= baseline)
+}
+
+/// Reports the spot price to the runtime, if it adjusts its threshold to the spot price.
+///
+/// The spot price is reported at most once per parachain block, signed with an authority key
+/// from the keystore.
+async fn report_spot_price<Config>(
+	parachain: &Config::P,
+	keystore: &KeystorePtr,
+	transaction_pool: &Arc<Config::ExPool>,
+	price: Config::Balance,
+	last_report: &Mutex<Option<<Config::Block as BlockT>::Hash>>,
+) -> Result<(), Box<dyn Error>>
+where
+	Config: OnDemandConfig,
+	RuntimeApiOf<Config>: OnDemandRuntimeApi<
+		Config::Block,
+		Config::Balance,
+		RelayBlockNumber,
+		Config::ThresholdParameter,
+	>,
+{
+	let best_hash = parachain.usage_info().chain.best_hash;
+	let supports_reports = parachain.runtime_api().has_api_with::<dyn OnDemandRuntimeApi<
+		Config::Block,
+		Config::Balance,
+		RelayBlockNumber,
+		Config::ThresholdParameter,
+	>, _>(best_hash, |version| version >= 7)?;
+	if !supports_reports {
+		return Ok(())
+	}
+
+	{
+		let mut last_report = last_report.lock().expect("Poisoned lock");
+		if *last_report == Some(best_hash) {
+			return Ok(())
+		}
+		*last_report = Some(best_hash);
+	}
+
+	let mut api = parachain.runtime_api();
+	api.register_extension(KeystoreExt::from(keystore.clone()));
+	let Some(report) = api.spot_price_report(best_hash, price)? else { return Ok(()) };
+
+	transaction_pool
+		.submit_one(best_hash, TransactionSource::Local, report)
+		.await
+		.map_err(|e| e.to_string())?;
+
+	log::debug!(target: LOG_TARGET, "Reported the spot price {}", price);
+
+	Ok(())
+}
+
+/// Returns all the on-demand settings of the runtime.
+///
+/// Runtimes implementing an older version of the `OnDemandRuntimeApi` are queried one setting at
+/// a time, leaving the settings they don't support unset.
+pub fn on_demand_settings<Block, P, Balance, Threshold>(
+	parachain: &P,
+	hash: Block::Hash,
+) -> Result<OnDemandSettings<Balance, RelayBlockNumber, Threshold>, sp_api::ApiError>
+where
+	Block: BlockT,
+	P: ProvideRuntimeApi<Block>,
+	P::Api: OnDemandRuntimeApi<Block, Balance, RelayBlockNumber, Threshold>,
+	Balance: Codec + MaybeDisplay,
+	Threshold: ThresholdParameterT,
+{
+	let api = parachain.runtime_api();
+	if api.has_api_with::<dyn OnDemandRuntimeApi<Block, Balance, RelayBlockNumber, Threshold>, _>(
+		hash,
+		|version| version >= 8,
+	)? {
+		return api.on_demand_config(hash)
+	}
+
+	let version = api
+		.api_version::<dyn OnDemandRuntimeApi<Block, Balance, RelayBlockNumber, Threshold>>(hash)?
+		.unwrap_or_default();
+
+	Ok(OnDemandSettings {
+		slot_width: api.slot_width(hash)?,
+		threshold_parameter: api.threshold_parameter(hash)?,
+		max_order_price: if version >= 3 { api.max_order_price(hash)? } else { None },
+		max_idle_relay_blocks: if version >= 4 { api.max_idle_relay_blocks(hash)? } else { None },
+		inbound_message_threshold: if version >= 5 {
+			api.inbound_message_threshold(hash)?
+		} else {
+			None
+		},
+		thresholds: if version >= 6 { api.thresholds(hash)? } else { Vec::new() },
+	})
+}
+
+/// Returns the payment accounts the runtime records for `authorities`.
+///
+/// Runtimes without payment accounts let every authority pay with the account of its own key.
+pub fn authority_payment_accounts<Block, P, Balance, Threshold>(
+	parachain: &P,
+	hash: Block::Hash,
+	authorities: &[impl ByteArray],
+) -> Result<PaymentAccounts, sp_api::ApiError>
+where
+	Block: BlockT,
+	P: ProvideRuntimeApi<Block>,
+	P::Api: OnDemandRuntimeApi<Block, Balance, RelayBlockNumber, Threshold>,
+	Balance: Codec + MaybeDisplay,
+	Threshold: ThresholdParameterT,
+{
+	let api = parachain.runtime_api();
+	if !api.has_api_with::<dyn OnDemandRuntimeApi<Block, Balance, RelayBlockNumber, Threshold>, _>(
+		hash,
+		|version| version >= 9,
+	)? {
+		return Ok(PaymentAccounts::default())
+	}
+
+	let mut mapping = Vec::new();
+	for authority in authorities {
+		if let Some(payer) = api.payment_account(hash, authority.to_raw_vec())? {
+			mapping.push((authority.to_raw_vec(), sr25519::Public::from_raw(payer.into())));
+		}
+	}
+
+	Ok(PaymentAccounts::new(mapping))
+}
+
 async fn new_best_heads(
 	relay_chain: impl RelayChainInterface + Clone,
 	para_id: ParaId,
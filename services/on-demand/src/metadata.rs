@@ -0,0 +1,306 @@
+//! Relay chain metadata used for constructing on-demand calls and decoding relay chain storage.
+//!
+//! Instead of relying on types generated from a static metadata file, the layout of the relevant
+//! relay chain pallets is read from the live relay runtime and validated every time the relay
+//! chain performs a runtime upgrade.
+
+use codec::{Decode, Encode};
+use cumulus_relay_chain_interface::RelayChainInterface;
+use sp_core::H256;
+use std::{
+	fmt,
+	sync::{Arc, Mutex},
+};
+use subxt::{
+	ext::scale_value::{self, Value, ValueDef},
+	Metadata,
+};
+
+/// The relay chain pallet responsible for on-demand coretime.
+pub const ON_DEMAND_PALLET: &str = "OnDemandAssignmentProvider";
+/// The call used for placing on-demand orders, allowing the payer to be reaped.
+pub const PLACE_ORDER_ALLOW_DEATH: &str = "place_order_allow_death";
+/// The call used for placing on-demand orders, keeping the payer alive.
+pub const PLACE_ORDER_KEEP_ALIVE: &str = "place_order_keep_alive";
+/// The storage item containing the on-demand queue status.
+pub const QUEUE_STATUS: &str = "QueueStatus";
+/// The event emitted when an on-demand order is placed.
+pub const ORDER_PLACED: &str = "OnDemandOrderPlaced";
+
+/// The relay chain pallet responsible for candidate inclusion.
+pub const PARA_INCLUSION_PALLET: &str = "ParaInclusion";
+/// The event emitted when a candidate gets backed.
+pub const CANDIDATE_BACKED: &str = "CandidateBacked";
+
+/// The relay chain pallet responsible for assigning cores.
+pub const CORETIME_ASSIGNMENT_PALLET: &str = "CoretimeAssignmentProvider";
+/// The storage item containing the core descriptors.
+pub const CORE_DESCRIPTORS: &str = "CoreDescriptors";
+
+/// The relay chain system pallet.
+pub const SYSTEM_PALLET: &str = "System";
+/// The storage item containing account information.
+pub const ACCOUNT: &str = "Account";
+
+/// The metadata version we know how to interpret.
+const METADATA_VERSION: u32 = 15;
+
+/// Decoded relay chain storage value.
+pub type DecodedValue = Value<u32>;
+
+#[derive(Debug)]
+pub enum MetadataError {
+	/// Failed to fetch the metadata or the runtime version from the relay chain.
+	Unavailable(String),
+	/// The relay chain doesn't provide the metadata version we support.
+	UnsupportedVersion(u32),
+	/// The relay chain runtime doesn't contain the expected pallet.
+	MissingPallet(&'static str),
+	/// The pallet doesn't contain the expected call.
+	MissingCall { pallet: &'static str, call: &'static str },
+	/// The pallet doesn't contain the expected storage item.
+	MissingStorage { pallet: &'static str, entry: &'static str },
+	/// The pallet doesn't contain the expected event.
+	MissingEvent { pallet: &'static str, event: &'static str },
+	/// The call exists, but its arguments differ from what we expect.
+	IncompatibleCall { pallet: &'static str, call: &'static str, expected: usize, found: usize },
+	/// Failed to decode a storage value with the relay chain metadata.
+	Decode(String),
+}
+
+impl fmt::Display for MetadataError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Unavailable(e) => write!(f, "Relay chain metadata unavailable: {}", e),
+			Self::UnsupportedVersion(v) =>
+				write!(f, "Relay chain doesn't support metadata version {}", v),
+			Self::MissingPallet(pallet) =>
+				write!(f, "Incompatible relay runtime: pallet `{}` not found", pallet),
+			Self::MissingCall { pallet, call } =>
+				write!(f, "Incompatible relay runtime: call `{}::{}` not found", pallet, call),
+			Self::MissingStorage { pallet, entry } =>
+				write!(f, "Incompatible relay runtime: storage `{}::{}` not found", pallet, entry),
+			Self::MissingEvent { pallet, event } =>
+				write!(f, "Incompatible relay runtime: event `{}::{}` not found", pallet, event),
+			Self::IncompatibleCall { pallet, call, expected, found } => write!(
+				f,
+				"Incompatible relay runtime: `{}::{}` expects {} arguments, found {}",
+				pallet, call, expected, found
+			),
+			Self::Decode(e) => write!(f, "Failed to decode relay chain storage: {}", e),
+		}
+	}
+}
+
+impl std::error::Error for MetadataError {}
+
+/// Metadata of a specific relay chain runtime version.
+pub struct RelayMetadata {
+	/// Spec version of the relay runtime this metadata belongs to.
+	spec_version: u32,
+	/// The runtime metadata.
+	metadata: Metadata,
+}
+
+impl RelayMetadata {
+	/// Fetches the metadata of the relay chain runtime at `hash`.
+	pub async fn fetch(
+		relay_chain: &(impl RelayChainInterface + Clone),
+		hash: H256,
+	) -> Result<Self, MetadataError> {
+		let spec_version = relay_chain
+			.version(hash)
+			.await
+			.map_err(|e| MetadataError::Unavailable(e.to_string()))?
+			.spec_version;
+
+		let raw = relay_chain
+			.call_runtime_api("Metadata_metadata_at_version", hash, &METADATA_VERSION.encode())
+			.await
+			.map_err(|e| MetadataError::Unavailable(e.to_string()))?;
+
+		let opaque = <Option<Vec<u8>>>::decode(&mut &raw[..])
+			.map_err(|e| MetadataError::Unavailable(e.to_string()))?
+			.ok_or(MetadataError::UnsupportedVersion(METADATA_VERSION))?;
+
+		let metadata = Metadata::decode(&mut &opaque[..])
+			.map_err(|e| MetadataError::Unavailable(e.to_string()))?;
+
+		Self::new(spec_version, metadata)
+	}
+
+	/// Wraps the provided metadata, ensuring the relay chain layout is compatible with the
+	/// service.
+	pub fn new(spec_version: u32, metadata: Metadata) -> Result<Self, MetadataError> {
+		let relay_metadata = Self { spec_version, metadata };
+		relay_metadata.validate()?;
+
+		Ok(relay_metadata)
+	}
+
+	/// Spec version of the relay runtime.
+	pub fn spec_version(&self) -> u32 {
+		self.spec_version
+	}
+
+	/// Decodes the raw value of a storage item using the relay chain metadata.
+	pub fn decode_storage(
+		&self,
+		pallet: &'static str,
+		entry: &'static str,
+		raw: &[u8],
+	) -> Result<DecodedValue, MetadataError> {
+		let value_ty = self
+			.metadata
+			.pallet_by_name(pallet)
+			.ok_or(MetadataError::MissingPallet(pallet))?
+			.storage()
+			.and_then(|storage| storage.entry_by_name(entry))
+			.ok_or(MetadataError::MissingStorage { pallet, entry })?
+			.entry_type()
+			.value_ty();
+
+		scale_value::scale::decode_as_type(&mut &raw[..], value_ty, self.metadata.types())
+			.map_err(|e| MetadataError::Decode(e.to_string()))
+	}
+
+	/// Ensures all the calls and storage items used by the service are present in the relay
+	/// runtime.
+	fn validate(&self) -> Result<(), MetadataError> {
+		self.ensure_call(ON_DEMAND_PALLET, PLACE_ORDER_ALLOW_DEATH, 2)?;
+		self.ensure_call(ON_DEMAND_PALLET, PLACE_ORDER_KEEP_ALIVE, 2)?;
+		self.ensure_storage(ON_DEMAND_PALLET, QUEUE_STATUS)?;
+		self.ensure_storage(CORETIME_ASSIGNMENT_PALLET, CORE_DESCRIPTORS)?;
+		self.ensure_storage(SYSTEM_PALLET, ACCOUNT)?;
+		self.ensure_event(ON_DEMAND_PALLET, ORDER_PLACED)?;
+		self.ensure_event(PARA_INCLUSION_PALLET, CANDIDATE_BACKED)?;
+
+		Ok(())
+	}
+
+	fn ensure_call(
+		&self,
+		pallet: &'static str,
+		call: &'static str,
+		arguments: usize,
+	) -> Result<(), MetadataError> {
+		let variant = self
+			.metadata
+			.pallet_by_name(pallet)
+			.ok_or(MetadataError::MissingPallet(pallet))?
+			.call_variant_by_name(call)
+			.ok_or(MetadataError::MissingCall { pallet, call })?;
+
+		if variant.fields.len() != arguments {
+			return Err(MetadataError::IncompatibleCall {
+				pallet,
+				call,
+				expected: arguments,
+				found: variant.fields.len(),
+			})
+		}
+
+		Ok(())
+	}
+
+	fn ensure_storage(
+		&self,
+		pallet: &'static str,
+		entry: &'static str,
+	) -> Result<(), MetadataError> {
+		self.metadata
+			.pallet_by_name(pallet)
+			.ok_or(MetadataError::MissingPallet(pallet))?
+			.storage()
+			.and_then(|storage| storage.entry_by_name(entry))
+			.ok_or(MetadataError::MissingStorage { pallet, entry })?;
+
+		Ok(())
+	}
+
+	fn ensure_event(&self, pallet: &'static str, event: &'static str) -> Result<(), MetadataError> {
+		self.metadata
+			.pallet_by_name(pallet)
+			.ok_or(MetadataError::MissingPallet(pallet))?
+			.event_variants()
+			.and_then(|variants| variants.iter().find(|variant| variant.name == event))
+			.ok_or(MetadataError::MissingEvent { pallet, event })?;
+
+		Ok(())
+	}
+}
+
+/// Keeps the relay chain metadata up to date with the relay runtime.
+///
+/// The metadata is only re-fetched when the relay chain spec version changes.
+#[derive(Default)]
+pub struct MetadataCache {
+	cached: Mutex<Option<Arc<RelayMetadata>>>,
+}
+
+impl MetadataCache {
+	/// Returns the metadata of the relay runtime at `hash`.
+	pub async fn at(
+		&self,
+		relay_chain: &(impl RelayChainInterface + Clone),
+		hash: H256,
+	) -> Result<Arc<RelayMetadata>, MetadataError> {
+		let spec_version = relay_chain
+			.version(hash)
+			.await
+			.map_err(|e| MetadataError::Unavailable(e.to_string()))?
+			.spec_version;
+
+		if let Some(cached) = self.cached.lock().expect("Poisoned lock").as_ref() {
+			if cached.spec_version() == spec_version {
+				return Ok(cached.clone())
+			}
+		}
+
+		let metadata = match RelayMetadata::fetch(relay_chain, hash).await {
+			Ok(metadata) => Arc::new(metadata),
+			Err(e) => {
+				log::error!(
+					target: crate::LOG_TARGET,
+					"Relay runtime {} is not supported by the on-demand service: {}",
+					spec_version,
+					e
+				);
+				return Err(e)
+			},
+		};
+
+		log::info!(
+			target: crate::LOG_TARGET,
+			"On-demand layout validated against relay runtime version {}",
+			spec_version
+		);
+
+		*self.cached.lock().expect("Poisoned lock") = Some(metadata.clone());
+		Ok(metadata)
+	}
+}
+
+/// Returns the value of the `Some` variant, or `None` if the value is `None`.
+pub fn as_option(value: &DecodedValue) -> Option<&DecodedValue> {
+	match &value.value {
+		ValueDef::Variant(variant) if variant.name == "Some" => variant.values.values().next(),
+		_ => None,
+	}
+}
+
+/// Returns the items of a sequence or a tuple.
+pub fn as_sequence(value: &DecodedValue) -> Vec<&DecodedValue> {
+	match &value.value {
+		ValueDef::Composite(composite) => composite.values().collect(),
+		_ => Vec::new(),
+	}
+}
+
+/// Returns the name of the variant, if the value is a variant.
+pub fn variant_name(value: &DecodedValue) -> Option<&str> {
+	match &value.value {
+		ValueDef::Variant(variant) => Some(variant.name.as_str()),
+		_ => None,
+	}
+}
@@ -0,0 +1,242 @@
+//! Prometheus metrics of the on-demand order placement service.
+
+use crate::config::Decision;
+use prometheus_endpoint::{
+	register, Counter, CounterVec, Gauge, GaugeVec, Opts, PrometheusError, Registry, U64,
+};
+use std::{
+	sync::{Arc, Mutex},
+	time::Instant,
+};
+
+/// Reasons for which an order can fail, used as metric labels.
+pub mod reason {
+	/// Failed to read the spot price from the relay chain.
+	pub const SPOT_PRICE: &str = "spot_price";
+	/// Failed to connect to the relay chain rpc node.
+	pub const CONNECTION: &str = "connection";
+	/// The relay chain rejected the order transaction.
+	pub const SUBMISSION: &str = "submission";
+	/// The order transaction was submitted, but didn't get placed.
+	pub const INCLUSION: &str = "inclusion";
+	/// The spot price exceeds the maximum order price set in the runtime.
+	pub const PRICE_CAP: &str = "price_cap";
+	/// Placing the order would exceed the spend budget of the collator.
+	pub const BUDGET: &str = "budget";
+}
+
+/// On-demand service metrics.
+#[derive(Clone)]
+pub struct Metrics {
+	orders_attempted: Counter<U64>,
+	orders_submitted: Counter<U64>,
+	orders_failed: CounterVec<U64>,
+	orders_placed: Counter<U64>,
+	spot_price: Gauge<U64>,
+	amount_spent: Counter<U64>,
+	threshold_progress: Gauge<U64>,
+	criteria_decision: GaugeVec<U64>,
+	criteria_value: GaugeVec<U64>,
+	criteria_threshold: GaugeVec<U64>,
+	relay_balance: GaugeVec<U64>,
+	since_last_order: Gauge<U64>,
+	errors: Counter<U64>,
+	task_restarts: CounterVec<U64>,
+	last_order: Arc<Mutex<Option<Instant>>>,
+}
+
+impl Metrics {
+	/// Registers the metrics in the provided registry.
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			orders_attempted: register(
+				Counter::new(
+					"on_demand_orders_attempted_total",
+					"Number of orders this collator attempted to submit to the relay chain",
+				)?,
+				registry,
+			)?,
+			orders_submitted: register(
+				Counter::new(
+					"on_demand_orders_submitted_total",
+					"Number of orders submitted to the relay chain",
+				)?,
+				registry,
+			)?,
+			orders_failed: register(
+				CounterVec::new(
+					Opts::new("on_demand_orders_failed_total", "Number of failed orders"),
+					&["reason"],
+				)?,
+				registry,
+			)?,
+			orders_placed: register(
+				Counter::new(
+					"on_demand_orders_placed_total",
+					"Number of orders placed on the relay chain",
+				)?,
+				registry,
+			)?,
+			spot_price: register(
+				Gauge::new("on_demand_spot_price", "Last on-demand spot price seen")?,
+				registry,
+			)?,
+			amount_spent: register(
+				Counter::new(
+					"on_demand_amount_spent_total",
+					"Total amount spent on on-demand orders",
+				)?,
+				registry,
+			)?,
+			threshold_progress: register(
+				Gauge::new(
+					"on_demand_threshold_progress_percent",
+					"Progress towards the order placement threshold",
+				)?,
+				registry,
+			)?,
+			criteria_decision: register(
+				GaugeVec::new(
+					Opts::new(
+						"on_demand_criteria_decision",
+						"Last decision of the order placement criteria, 1 if an order is to be placed",
+					),
+					&["reason"],
+				)?,
+				registry,
+			)?,
+			criteria_value: register(
+				GaugeVec::new(
+					Opts::new(
+						"on_demand_criteria_value",
+						"Last value measured by the order placement criteria",
+					),
+					&["criteria"],
+				)?,
+				registry,
+			)?,
+			criteria_threshold: register(
+				GaugeVec::new(
+					Opts::new(
+						"on_demand_criteria_threshold",
+						"Last threshold the measured value was compared against",
+					),
+					&["criteria"],
+				)?,
+				registry,
+			)?,
+			relay_balance: register(
+				GaugeVec::new(
+					Opts::new(
+						"on_demand_relay_balance",
+						"Free relay chain balance of the collator accounts",
+					),
+					&["account"],
+				)?,
+				registry,
+			)?,
+			since_last_order: register(
+				Gauge::new(
+					"on_demand_seconds_since_last_order",
+					"Time elapsed since the last successfully placed order",
+				)?,
+				registry,
+			)?,
+			errors: register(
+				Counter::new(
+					"on_demand_errors_total",
+					"Number of relay chain blocks for which order placement failed with an error",
+				)?,
+				registry,
+			)?,
+			task_restarts: register(
+				CounterVec::new(
+					Opts::new("on_demand_task_restarts_total", "Number of on-demand task restarts"),
+					&["task"],
+				)?,
+				registry,
+			)?,
+			last_order: Default::default(),
+		})
+	}
+
+	/// An order is about to be submitted to the relay chain.
+	pub fn order_attempted(&self) {
+		self.orders_attempted.inc();
+	}
+
+	/// An order got submitted to the relay chain.
+	pub fn order_submitted(&self) {
+		self.orders_submitted.inc();
+	}
+
+	/// An order failed for the given reason.
+	pub fn order_failed(&self, reason: &str) {
+		self.orders_failed.with_label_values(&[reason]).inc();
+	}
+
+	/// An order got placed and `spent` was paid for it.
+	pub fn order_placed(&self, spent: u128) {
+		self.orders_placed.inc();
+		self.amount_spent.inc_by(saturate(spent));
+		*self.last_order.lock().expect("Poisoned lock") = Some(Instant::now());
+		self.since_last_order.set(0);
+	}
+
+	/// Reports the last spot price read from the relay chain.
+	pub fn spot_price(&self, price: u128) {
+		self.spot_price.set(saturate(price));
+	}
+
+	/// Reports the progress towards the order placement threshold.
+	pub fn threshold_progress(&self, percent: u32) {
+		self.threshold_progress.set(percent.into());
+	}
+
+	/// Reports the last decision of the order placement criteria, along with the values it is
+	/// based on.
+	///
+	/// Only the criteria which determined the latest decision is kept as reason.
+	pub fn criteria_decision(&self, decision: &Decision) {
+		self.criteria_decision.reset();
+		self.criteria_decision
+			.with_label_values(&[decision.code.as_str()])
+			.set(decision.place_order.into());
+
+		for measurement in &decision.measurements {
+			let criteria = measurement.code.as_str();
+			self.criteria_value
+				.with_label_values(&[criteria])
+				.set(saturate(measurement.value));
+			self.criteria_threshold
+				.with_label_values(&[criteria])
+				.set(saturate(measurement.threshold));
+		}
+	}
+
+	/// Reports the free relay chain balance of a collator account.
+	pub fn relay_balance(&self, account: &str, free: u128) {
+		self.relay_balance.with_label_values(&[account]).set(saturate(free));
+	}
+
+	/// Handling a relay chain block failed with an error.
+	pub fn error(&self) {
+		self.errors.inc();
+	}
+
+	/// An on-demand task stopped and is going to be restarted.
+	pub fn task_restarted(&self, task: &str) {
+		self.task_restarts.with_label_values(&[task]).inc();
+	}
+
+	/// Refreshes the time elapsed since the last successfully placed order.
+	pub fn refresh_since_last_order(&self) {
+		if let Some(last_order) = *self.last_order.lock().expect("Poisoned lock") {
+			self.since_last_order.set(last_order.elapsed().as_secs());
+		}
+	}
+}
+
+fn saturate(value: u128) -> u64 {
+	value.try_into().unwrap_or(u64::MAX)
+}
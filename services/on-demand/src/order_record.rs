@@ -0,0 +1,146 @@
+//! In-memory record of the on-demand orders placed by this collator.
+
+use cumulus_primitives_core::relay_chain::BlockNumber as RelayBlockNumber;
+use sp_core::H256;
+use std::{
+	collections::VecDeque,
+	sync::{Arc, Mutex},
+};
+
+/// The maximum number of orders we keep track of.
+const MAX_RECORDS: usize = 64;
+
+/// The number of relay chain blocks after which a submitted order, which didn't get included, is
+/// considered lost.
+pub const INCLUSION_TIMEOUT: RelayBlockNumber = 10;
+
+/// The lifecycle stage of an order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderStatus {
+	/// The order was submitted to the relay chain.
+	Submitted,
+	/// The order got included in a relay chain block.
+	Placed {
+		/// The relay chain block containing the order.
+		relay_block: H256,
+		/// The number of the relay chain block containing the order.
+		relay_height: RelayBlockNumber,
+		/// The amount that was actually spent on the order.
+		spent: u128,
+	},
+	/// A parachain block was backed on a core following the order.
+	Backed {
+		/// The relay chain block in which the candidate got backed.
+		relay_block: H256,
+		/// The core on which the candidate got backed.
+		core: u32,
+	},
+	/// The order failed.
+	Failed(String),
+}
+
+/// Information about a single on-demand order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderRecord {
+	/// Hash of the order extrinsic.
+	pub tx_hash: H256,
+	/// The relay chain block number at which the order was created.
+	pub relay_height: RelayBlockNumber,
+	/// The maximum amount we were willing to pay for the order.
+	pub max_amount: u128,
+	/// The cores serving the on-demand pool when the order was created.
+	pub cores: Vec<u32>,
+	/// The current stage of the order.
+	pub status: OrderStatus,
+}
+
+/// Shared record of the most recent orders.
+#[derive(Default, Clone)]
+pub struct OrderRecords(Arc<Mutex<VecDeque<OrderRecord>>>);
+
+impl OrderRecords {
+	/// Records a newly submitted order.
+	pub fn submitted(
+		&self,
+		tx_hash: H256,
+		relay_height: RelayBlockNumber,
+		max_amount: u128,
+		cores: Vec<u32>,
+	) {
+		let mut records = self.0.lock().expect("Poisoned lock");
+		if records.len() >= MAX_RECORDS {
+			records.pop_front();
+		}
+		records.push_back(OrderRecord {
+			tx_hash,
+			relay_height,
+			max_amount,
+			cores,
+			status: OrderStatus::Submitted,
+		});
+	}
+
+	/// Marks the order as included in `relay_block`.
+	pub fn placed(
+		&self,
+		tx_hash: H256,
+		relay_block: H256,
+		relay_height: RelayBlockNumber,
+		spent: u128,
+	) {
+		self.update(tx_hash, OrderStatus::Placed { relay_block, relay_height, spent });
+	}
+
+	/// Marks the order as failed.
+	pub fn failed(&self, tx_hash: H256, reason: String) {
+		self.update(tx_hash, OrderStatus::Failed(reason));
+	}
+
+	/// Returns whether an order was submitted, but not yet included in a relay chain block.
+	///
+	/// Orders not included within `INCLUSION_TIMEOUT` relay chain blocks are marked as failed, so
+	/// a lost transaction doesn't prevent placing further orders.
+	pub fn awaiting_inclusion(&self, now: RelayBlockNumber) -> bool {
+		let mut records = self.0.lock().expect("Poisoned lock");
+		let mut awaiting = false;
+		for record in records.iter_mut().filter(|record| record.status == OrderStatus::Submitted) {
+			if now.saturating_sub(record.relay_height) < INCLUSION_TIMEOUT {
+				awaiting = true;
+			} else {
+				record.status = OrderStatus::Failed(format!(
+					"Not included within {} relay blocks",
+					INCLUSION_TIMEOUT
+				));
+			}
+		}
+
+		awaiting
+	}
+
+	/// Marks the oldest order placed on `core` before `relay_height` as backed.
+	///
+	/// Returns the record of the order if there was one awaiting backing.
+	pub fn backed(
+		&self,
+		relay_block: H256,
+		relay_height: RelayBlockNumber,
+		core: u32,
+	) -> Option<OrderRecord> {
+		let mut records = self.0.lock().expect("Poisoned lock");
+		let record = records.iter_mut().find(|record| match record.status {
+			OrderStatus::Placed { relay_height: placed_at, .. } =>
+				placed_at < relay_height && record.cores.contains(&core),
+			_ => false,
+		})?;
+
+		record.status = OrderStatus::Backed { relay_block, core };
+		Some(record.clone())
+	}
+
+	fn update(&self, tx_hash: H256, status: OrderStatus) {
+		let mut records = self.0.lock().expect("Poisoned lock");
+		if let Some(record) = records.iter_mut().find(|record| record.tx_hash == tx_hash) {
+			record.status = status;
+		}
+	}
+}
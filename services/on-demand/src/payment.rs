@@ -0,0 +1,112 @@
+//! Relay chain accounts paying for on-demand orders.
+//!
+//! Orders are paid with a dedicated key rather than with the consensus key of the collator. The
+//! payment key has to be inserted into the keystore under [`KEY_TYPE`].
+
+use sp_core::{
+	crypto::{ByteArray, KeyTypeId},
+	sr25519,
+};
+use sp_keystore::KeystorePtr;
+use std::{collections::BTreeMap, fmt};
+use subxt::utils::{AccountId32, MultiSignature};
+
+/// Key type of the keys paying for on-demand orders.
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"odpy");
+
+/// Maps authorities to the relay chain accounts paying for their orders.
+///
+/// The mapping is kept on-chain, so all collators agree on who can afford an order. Authorities
+/// without an explicit mapping pay with the account of their own public key. Their key still has
+/// to be inserted under [`KEY_TYPE`] for the collator to sign with it.
+#[derive(Debug, Clone, Default)]
+pub struct PaymentAccounts(BTreeMap<Vec<u8>, sr25519::Public>);
+
+impl PaymentAccounts {
+	pub fn new(mapping: impl IntoIterator<Item = (Vec<u8>, sr25519::Public)>) -> Self {
+		Self(mapping.into_iter().collect())
+	}
+
+	/// Returns the payment key of the authority with the given raw public key.
+	pub fn payer(&self, authority: &[u8]) -> Option<sr25519::Public> {
+		match self.0.get(authority) {
+			Some(payer) => Some(*payer),
+			None => sr25519::Public::from_slice(authority).ok(),
+		}
+	}
+}
+
+/// Errors which can occur when signing an order.
+#[derive(Debug)]
+pub enum SigningError {
+	/// The authority has no payment account.
+	NoPaymentAccount,
+	/// The payment key isn't in the keystore.
+	MissingKey(sr25519::Public),
+	/// The keystore failed to sign.
+	Keystore(sp_keystore::Error),
+}
+
+impl fmt::Display for SigningError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::NoPaymentAccount => write!(f, "The authority has no payment account"),
+			Self::MissingKey(public) =>
+				write!(f, "Payment key {} not found in the keystore", public),
+			Self::Keystore(e) => write!(f, "Failed to sign with the payment key: {}", e),
+		}
+	}
+}
+
+impl std::error::Error for SigningError {}
+
+/// Signs relay chain transactions with a payment key from the keystore.
+pub struct PaymentSigner {
+	public: sr25519::Public,
+	keystore: KeystorePtr,
+}
+
+impl PaymentSigner {
+	/// Creates a signer for `public`, which must be in the keystore under [`KEY_TYPE`].
+	pub fn new(keystore: KeystorePtr, public: sr25519::Public) -> Result<Self, SigningError> {
+		if !keystore.has_keys(&[(public.to_raw_vec(), KEY_TYPE)]) {
+			return Err(SigningError::MissingKey(public))
+		}
+
+		Ok(Self { public, keystore })
+	}
+
+	/// Creates a signer for the payment key of the authority with the given raw public key.
+	///
+	/// Collators may hold the keys of several authorities, so the signer must be created for the
+	/// authority elected to place the order.
+	pub fn for_authority(
+		keystore: KeystorePtr,
+		payment_accounts: &PaymentAccounts,
+		authority: &[u8],
+	) -> Result<Self, SigningError> {
+		let payer = payment_accounts.payer(authority).ok_or(SigningError::NoPaymentAccount)?;
+		Self::new(keystore, payer)
+	}
+
+	/// The payment key.
+	pub fn public(&self) -> sr25519::Public {
+		self.public
+	}
+
+	/// The relay chain account paying for the orders.
+	pub fn account_id(&self) -> AccountId32 {
+		AccountId32(self.public.0)
+	}
+
+	/// Signs the payload of a relay chain transaction.
+	pub fn sign(&self, payload: &[u8]) -> Result<MultiSignature, SigningError> {
+		let signature = self
+			.keystore
+			.sr25519_sign(KEY_TYPE, &self.public, payload)
+			.map_err(SigningError::Keystore)?
+			.ok_or(SigningError::MissingKey(self.public))?;
+
+		Ok(MultiSignature::Sr25519(signature.0))
+	}
+}
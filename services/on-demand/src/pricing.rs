@@ -0,0 +1,148 @@
+//! Reproduces the on-demand spot price calculation of the relay chain.
+//!
+//! The relay chain keeps a `traffic` multiplier which is updated every block based on how full
+//! the on-demand queue is compared to the targeted utilisation:
+//!
+//! - `traffic' = traffic * (|u - u*| + (v * (u - u*))^2 / 2 + 1)` if `u >= u*`
+//! - `traffic' = traffic * (|u - u*| - (v * (u - u*))^2 / 2)` otherwise
+//!
+//! where `v` is the fee variability, `u` the current queue utilisation and `u*` the target queue
+//! utilisation. The result never drops below the default traffic. The spot price is
+//! `traffic * base_fee`.
+//!
+//! This mirrors `calculate_spot_traffic` of the relay chain `on_demand` pallet operation by
+//! operation, so that the rounding matches as well.
+
+use polkadot_runtime_parachains::configuration::HostConfiguration;
+use sp_runtime::{
+	traits::{One, Saturating},
+	FixedPointNumber, FixedU128, Perbill, SaturatedConversion,
+};
+
+/// The default value of the traffic multiplier on the relay chain.
+///
+/// The traffic never drops below this value.
+pub const MIN_TRAFFIC: FixedU128 = FixedU128::from_u32(1);
+
+/// Errors which can occur when computing the traffic multiplier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PricingError {
+	/// The on-demand queue has no capacity.
+	QueueCapacityIsZero,
+	/// The queue contains more orders than its capacity.
+	QueueSizeLargerThanCapacity,
+	/// Division overflowed.
+	Division,
+}
+
+impl std::fmt::Display for PricingError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::QueueCapacityIsZero => write!(f, "The on-demand queue has no capacity"),
+			Self::QueueSizeLargerThanCapacity =>
+				write!(f, "The on-demand queue size exceeds its capacity"),
+			Self::Division => write!(f, "Division overflowed while computing the traffic"),
+		}
+	}
+}
+
+impl std::error::Error for PricingError {}
+
+/// The pricing parameters of the relay chain, as found in `HostConfiguration.scheduler_params`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PricingParams {
+	/// The base fee of an on-demand order.
+	pub base_fee: u128,
+	/// The maximum size of the on-demand queue.
+	pub queue_capacity: u32,
+	/// The queue utilisation at which the traffic stays unchanged.
+	pub target_queue_utilisation: Perbill,
+	/// How fast the traffic reacts to the queue utilisation diverging from the target.
+	pub fee_variability: Perbill,
+}
+
+impl PricingParams {
+	/// Reads the pricing parameters from the relay chain configuration.
+	pub fn from_config<BlockNumber>(config: &HostConfiguration<BlockNumber>) -> Self {
+		let params = &config.scheduler_params;
+		Self {
+			base_fee: params.on_demand_base_fee.saturated_into(),
+			queue_capacity: params.on_demand_queue_max_size,
+			target_queue_utilisation: params.on_demand_target_queue_utilization,
+			fee_variability: params.on_demand_fee_variability,
+		}
+	}
+}
+
+/// The state of the on-demand queue which determines the spot price.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PricingState {
+	/// The current traffic multiplier.
+	pub traffic: FixedU128,
+	/// The number of orders in the on-demand queue.
+	pub queue_size: u32,
+	/// The pricing parameters of the relay chain.
+	pub params: PricingParams,
+}
+
+impl PricingState {
+	/// The current spot price.
+	pub fn spot_price(&self) -> u128 {
+		spot_price(self.traffic, self.params.base_fee)
+	}
+
+	/// Estimates the spot price `blocks` relay chain blocks ahead.
+	///
+	/// Assumes the queue size stays the same in the meantime.
+	pub fn predict_spot_price(&self, blocks: u32) -> Result<u128, PricingError> {
+		let mut traffic = self.traffic;
+		for _ in 0..blocks {
+			traffic = next_traffic(traffic, self.queue_size, &self.params)?;
+		}
+
+		Ok(spot_price(traffic, self.params.base_fee))
+	}
+}
+
+/// Computes the spot price from the traffic multiplier and the base fee.
+pub fn spot_price(traffic: FixedU128, base_fee: u128) -> u128 {
+	traffic.saturating_mul_int(base_fee)
+}
+
+/// Computes the traffic multiplier of the next relay chain block.
+pub fn next_traffic(
+	traffic: FixedU128,
+	queue_size: u32,
+	params: &PricingParams,
+) -> Result<FixedU128, PricingError> {
+	if params.queue_capacity == 0 {
+		return Err(PricingError::QueueCapacityIsZero)
+	}
+	if queue_size > params.queue_capacity {
+		return Err(PricingError::QueueSizeLargerThanCapacity)
+	}
+
+	// (queue_size / queue_capacity) - target_queue_utilisation
+	let target = FixedU128::from(params.target_queue_utilisation);
+	let utilisation = FixedU128::from_rational(queue_size.into(), params.queue_capacity.into());
+	let positive = utilisation >= target;
+	let diff = utilisation.max(target) - utilisation.min(target);
+
+	// variability * diff
+	let var_times_diff = diff.saturating_mul(params.fee_variability.into());
+	// (variability^2 * diff^2) / 2
+	let div_by_two = var_times_diff
+		.saturating_mul(var_times_diff)
+		.const_checked_div(2.into())
+		.ok_or(PricingError::Division)?;
+
+	let next = if positive {
+		diff.saturating_add(div_by_two)
+			.saturating_add(One::one())
+			.saturating_mul(traffic)
+	} else {
+		diff.saturating_sub(div_by_two).saturating_mul(traffic)
+	};
+
+	Ok(next.max(MIN_TRAFFIC))
+}
@@ -0,0 +1,262 @@
+//! On-demand settings of the parachain runtime, kept up to date by following parachain blocks.
+//!
+//! Instead of querying the runtime on every relay chain block, the settings are fetched once and
+//! then updated from the `pallet-on-demand` events of the imported parachain blocks. All settings
+//! are re-fetched on reorgs and runtime upgrades, or when an event can't be interpreted.
+
+use crate::{on_demand_settings, RelayBlockNumber, LOG_TARGET};
+use codec::{Codec, Decode};
+use futures::StreamExt;
+use on_demand_primitives::{
+	OnDemandRuntimeApi, OnDemandSettings, ThresholdKind, ThresholdParameterT,
+};
+use sc_client_api::{Backend as BackendT, BlockchainEvents, StorageProvider, UsageProvider};
+use sp_api::{Core, Metadata as MetadataApi, ProvideRuntimeApi};
+use sp_core::{hashing::twox_128, storage::StorageKey};
+use sp_runtime::traits::{Block as BlockT, MaybeDisplay};
+use std::{error::Error, sync::Mutex};
+use subxt::{events::Events, Metadata, PolkadotConfig};
+
+/// The name of `pallet-on-demand` in the parachain runtime.
+pub const ON_DEMAND_PALLET: &str = "OnDemand";
+/// The event emitted when the slot width is set.
+pub const SLOT_WIDTH_SET: &str = "SlotWidthSet";
+/// The event emitted when the threshold parameter is set.
+pub const THRESHOLD_PARAMETER_SET: &str = "ThresholdParameterSet";
+/// The event emitted when a threshold of any kind is set.
+pub const THRESHOLD_SET: &str = "ThresholdSet";
+/// Events emitted when any of the other settings is set.
+pub const OTHER_SETTINGS_SET: &[&str] =
+	&["MaxOrderPriceSet", "MaxIdleRelayBlocksSet", "InboundMessageThresholdSet"];
+
+/// The metadata version we know how to interpret.
+const METADATA_VERSION: u32 = 15;
+
+/// The on-demand settings of the parachain runtime.
+pub type Settings<Balance, Threshold> = OnDemandSettings<Balance, RelayBlockNumber, Threshold>;
+
+/// A change of the on-demand settings, decoded from a parachain event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SettingsChange<Threshold> {
+	/// The slot width was set.
+	SlotWidth(RelayBlockNumber),
+	/// The threshold parameter was set.
+	ThresholdParameter(Threshold),
+	/// The threshold of the given kind was set, or removed if `None`.
+	Threshold(ThresholdKind, Option<Threshold>),
+	/// Any of the other settings was set, requiring all settings to be re-fetched.
+	Other,
+}
+
+/// The last known on-demand settings of the best parachain block.
+pub struct SettingsCache<Balance, Threshold> {
+	cached: Mutex<Option<Settings<Balance, Threshold>>>,
+}
+
+impl<Balance, Threshold> Default for SettingsCache<Balance, Threshold> {
+	fn default() -> Self {
+		Self { cached: Mutex::new(None) }
+	}
+}
+
+impl<Balance: Clone, Threshold: ThresholdParameterT> SettingsCache<Balance, Threshold> {
+	/// Returns the cached settings, if any.
+	pub fn get(&self) -> Option<Settings<Balance, Threshold>> {
+		self.cached.lock().expect("Poisoned lock").clone()
+	}
+
+	/// Replaces the cached settings, logging the changes compared to the previous ones.
+	pub fn set(&self, settings: Settings<Balance, Threshold>) {
+		let mut cached = self.cached.lock().expect("Poisoned lock");
+		if let Some(previous) = cached.as_ref() {
+			if previous.slot_width != settings.slot_width {
+				log_change("slot width", &previous.slot_width, &settings.slot_width);
+			}
+			if previous.threshold_parameter != settings.threshold_parameter {
+				log_change(
+					"threshold parameter",
+					&previous.threshold_parameter,
+					&settings.threshold_parameter,
+				);
+			}
+		}
+		*cached = Some(settings);
+	}
+
+	/// Applies the change to the cached settings.
+	///
+	/// Returns `false` if the change can't be applied, in which case the settings have to be
+	/// re-fetched.
+	pub fn apply(&self, change: SettingsChange<Threshold>) -> bool {
+		let mut cached = self.cached.lock().expect("Poisoned lock");
+		let Some(settings) = cached.as_mut() else { return false };
+
+		match change {
+			SettingsChange::SlotWidth(width) => {
+				log_change("slot width", &settings.slot_width, &width);
+				settings.slot_width = width;
+			},
+			SettingsChange::ThresholdParameter(parameter) => {
+				log_change("threshold parameter", &settings.threshold_parameter, &parameter);
+				settings.threshold_parameter = parameter;
+			},
+			SettingsChange::Threshold(kind, value) => {
+				let position = settings.thresholds.binary_search_by_key(&kind, |(k, _)| *k);
+				let previous = position.ok().map(|i| settings.thresholds[i].1.clone());
+				log_change(&format!("{:?} threshold", kind), &previous, &value);
+				match (position, value) {
+					(Ok(i), Some(value)) => settings.thresholds[i].1 = value,
+					(Ok(i), None) => {
+						settings.thresholds.remove(i);
+					},
+					(Err(i), Some(value)) => settings.thresholds.insert(i, (kind, value)),
+					(Err(_), None) => {},
+				}
+			},
+			SettingsChange::Other => return false,
+		}
+
+		true
+	}
+}
+
+fn log_change(setting: &str, from: &impl core::fmt::Debug, to: &impl core::fmt::Debug) {
+	log::info!(
+		target: LOG_TARGET,
+		"On-demand {} changed from {:?} to {:?}",
+		setting,
+		from,
+		to
+	);
+}
+
+/// Metadata of a specific parachain runtime version.
+struct ParachainMetadata {
+	/// Spec version of the parachain runtime this metadata belongs to.
+	spec_version: u32,
+	/// The runtime metadata.
+	metadata: Metadata,
+}
+
+impl ParachainMetadata {
+	/// Fetches the metadata of the parachain runtime at `hash`.
+	fn fetch<Block, P>(parachain: &P, hash: Block::Hash) -> Result<Self, Box<dyn Error>>
+	where
+		Block: BlockT,
+		P: ProvideRuntimeApi<Block>,
+		P::Api: Core<Block> + MetadataApi<Block>,
+	{
+		let api = parachain.runtime_api();
+		let spec_version = api.version(hash)?.spec_version;
+		let opaque = api.metadata_at_version(hash, METADATA_VERSION)?.ok_or_else(|| {
+			format!("Parachain doesn't support metadata version {}", METADATA_VERSION)
+		})?;
+		let metadata = Metadata::decode(&mut &opaque[..])?;
+
+		Ok(Self { spec_version, metadata })
+	}
+}
+
+/// Keeps the settings cache up to date with the best parachain block.
+///
+/// Returns once the parachain stops producing import notifications.
+pub async fn follow_parachain<Block, P, Backend, Balance, Threshold>(
+	parachain: &P,
+	cache: &SettingsCache<Balance, Threshold>,
+) -> Result<(), Box<dyn Error>>
+where
+	Block: BlockT,
+	P: ProvideRuntimeApi<Block>
+		+ UsageProvider<Block>
+		+ StorageProvider<Block, Backend>
+		+ BlockchainEvents<Block>,
+	P::Api: OnDemandRuntimeApi<Block, Balance, RelayBlockNumber, Threshold>
+		+ Core<Block>
+		+ MetadataApi<Block>,
+	Backend: BackendT<Block>,
+	Balance: Codec + MaybeDisplay + Clone,
+	Threshold: ThresholdParameterT,
+{
+	let mut imports = parachain.import_notification_stream();
+
+	let best_hash = parachain.usage_info().chain.best_hash;
+	let mut metadata = ParachainMetadata::fetch::<Block, P>(parachain, best_hash)?;
+	cache.set(on_demand_settings::<Block, P, Balance, Threshold>(parachain, best_hash)?);
+
+	while let Some(notification) = imports.next().await {
+		if !notification.is_new_best {
+			continue
+		}
+		let hash = notification.hash;
+
+		let spec_version = parachain.runtime_api().version(hash)?.spec_version;
+		let upgraded = spec_version != metadata.spec_version;
+		if upgraded {
+			metadata = ParachainMetadata::fetch::<Block, P>(parachain, hash)?;
+		}
+
+		// Reorgs may revert changes, and runtime upgrades may migrate the settings.
+		let mut up_to_date = !upgraded && notification.tree_route.is_none();
+		if up_to_date {
+			let changes =
+				block_changes::<Block, P, Backend, Threshold>(parachain, hash, &metadata.metadata)
+					.unwrap_or_else(|e| {
+						log::warn!(
+							target: LOG_TARGET,
+							"Failed to read the on-demand events of parachain block {}: {}",
+							hash,
+							e
+						);
+						vec![SettingsChange::Other]
+					});
+			up_to_date = changes.into_iter().all(|change| cache.apply(change));
+		}
+
+		if !up_to_date {
+			cache.set(on_demand_settings::<Block, P, Balance, Threshold>(parachain, hash)?);
+		}
+	}
+
+	Ok(())
+}
+
+/// Returns the on-demand settings changes made in the parachain block.
+fn block_changes<Block, P, Backend, Threshold>(
+	parachain: &P,
+	hash: Block::Hash,
+	metadata: &Metadata,
+) -> Result<Vec<SettingsChange<Threshold>>, Box<dyn Error>>
+where
+	Block: BlockT,
+	P: StorageProvider<Block, Backend>,
+	Backend: BackendT<Block>,
+	Threshold: ThresholdParameterT,
+{
+	let key = [twox_128(b"System"), twox_128(b"Events")].concat();
+	let Some(raw) = parachain.storage(hash, &StorageKey(key))? else { return Ok(Vec::new()) };
+
+	let events = Events::<PolkadotConfig>::decode_from(raw.0, metadata.clone());
+	let mut changes = Vec::new();
+	for event in events.iter() {
+		let event = event?;
+		if event.pallet_name() != ON_DEMAND_PALLET {
+			continue
+		}
+
+		let mut fields = event.field_bytes();
+		match event.variant_name() {
+			SLOT_WIDTH_SET =>
+				changes.push(SettingsChange::SlotWidth(RelayBlockNumber::decode(&mut fields)?)),
+			THRESHOLD_PARAMETER_SET =>
+				changes.push(SettingsChange::ThresholdParameter(Threshold::decode(&mut fields)?)),
+			THRESHOLD_SET => changes.push(SettingsChange::Threshold(
+				ThresholdKind::decode(&mut fields)?,
+				Option::<Threshold>::decode(&mut fields)?,
+			)),
+			name if OTHER_SETTINGS_SET.contains(&name) => changes.push(SettingsChange::Other),
+			_ => {},
+		}
+	}
+
+	Ok(changes)
+}
@@ -0,0 +1,610 @@
+use crate::{
+	budget::{SpendBudget, SpendTracker},
+	chain::{pool_core, CoreQueue, PoolCore},
+	config::{collator_selection_authorities, elect_order_placer, Decision, ReasonCode},
+	criteria::percentage,
+	election::{AuraElection, PlacerElection, RoundRobin},
+	fallback::{EligiblePlacer, HeadTracker, PlacerFallback},
+	metrics::Metrics,
+	order_record::{OrderRecords, OrderStatus, INCLUSION_TIMEOUT},
+	payment::{PaymentAccounts, PaymentSigner, SigningError, KEY_TYPE},
+	pricing::{next_traffic, spot_price, PricingError, PricingParams, PricingState},
+	settings::{Settings, SettingsCache, SettingsChange},
+};
+use codec::Encode;
+use cumulus_primitives_core::relay_chain::CoreIndex;
+use on_demand_primitives::ThresholdKind;
+use scale_info::{MetaType, PortableRegistry, Registry};
+use sp_application_crypto::key_types::AURA;
+use sp_consensus_aura::{sr25519::AuthorityId as AuraId, Slot, AURA_ENGINE_ID};
+use sp_core::{
+	hashing::{twox_128, twox_64},
+	sr25519, ByteArray, Pair, H256,
+};
+use sp_keystore::{testing::MemoryKeystore, Keystore, KeystorePtr};
+use sp_runtime::{
+	generic::{Digest, DigestItem, Header},
+	traits::BlakeTwo256,
+	FixedU128, Perbill,
+};
+use std::{collections::BTreeMap, sync::Arc};
+use subxt::{
+	ext::scale_value,
+	utils::{AccountId32, MultiSignature},
+};
+
+type TestHeader = Header<u32, BlakeTwo256>;
+
+/// A parachain header authored in the given Aura slot.
+fn para_header(slot: Option<u64>) -> TestHeader {
+	let logs = slot
+		.map(|slot| DigestItem::PreRuntime(AURA_ENGINE_ID, Slot::from(slot).encode()))
+		.into_iter()
+		.collect();
+	TestHeader::new(1, H256::zero(), H256::zero(), H256::zero(), Digest { logs })
+}
+
+/// Default relay chain pricing parameters, with a small queue.
+fn params(queue_capacity: u32) -> PricingParams {
+	PricingParams {
+		base_fee: 10_000_000,
+		queue_capacity,
+		target_queue_utilisation: Perbill::from_percent(25),
+		fee_variability: Perbill::from_percent(3),
+	}
+}
+
+#[test]
+fn spot_price_works() {
+	// With the default traffic the spot price equals the base fee.
+	assert_eq!(spot_price(FixedU128::from_u32(1), 10_000_000), 10_000_000);
+	assert_eq!(spot_price(FixedU128::from_rational(3, 2), 10_000_000), 15_000_000);
+	// Rounds down like the relay chain.
+	assert_eq!(
+		spot_price(FixedU128::from_inner(1_750_253_125_000_000_000), 10_000_000),
+		17_502_531
+	);
+}
+
+// The expected traffic values below were produced by the relay chain `calculate_spot_traffic`
+// with `TrafficDefaultValue` set to 1.
+
+#[test]
+fn next_traffic_increases_above_target() {
+	// Full queue.
+	assert_eq!(
+		next_traffic(FixedU128::from_u32(1), 10, &params(10)),
+		Ok(FixedU128::from_inner(1_750_253_125_000_000_000))
+	);
+	// Half full queue.
+	assert_eq!(
+		next_traffic(FixedU128::from_u32(1), 5, &params(10)),
+		Ok(FixedU128::from_inner(1_250_028_125_000_000_000))
+	);
+}
+
+#[test]
+fn next_traffic_decreases_below_target() {
+	// Empty queue.
+	assert_eq!(
+		next_traffic(FixedU128::from_u32(10), 0, &params(10)),
+		Ok(FixedU128::from_inner(2_499_718_750_000_000_000))
+	);
+}
+
+#[test]
+fn next_traffic_unchanged_at_target() {
+	let traffic = FixedU128::from_u32(2);
+	assert_eq!(next_traffic(traffic, 250, &params(1000)), Ok(traffic));
+}
+
+#[test]
+fn next_traffic_never_drops_below_default() {
+	assert_eq!(next_traffic(FixedU128::from_u32(1), 0, &params(10)), Ok(FixedU128::from_u32(1)));
+	assert_eq!(next_traffic(FixedU128::from_u32(2), 0, &params(10)), Ok(FixedU128::from_u32(1)));
+}
+
+#[test]
+fn next_traffic_rejects_invalid_queue() {
+	assert_eq!(
+		next_traffic(FixedU128::from_u32(1), 0, &params(0)),
+		Err(PricingError::QueueCapacityIsZero)
+	);
+	assert_eq!(
+		next_traffic(FixedU128::from_u32(1), 11, &params(10)),
+		Err(PricingError::QueueSizeLargerThanCapacity)
+	);
+}
+
+#[test]
+fn predict_spot_price_works() {
+	let state =
+		PricingState { traffic: FixedU128::from_u32(1), queue_size: 10, params: params(10) };
+
+	assert_eq!(state.predict_spot_price(0), Ok(state.spot_price()));
+	assert_eq!(state.predict_spot_price(1), Ok(17_502_531));
+	// Traffic after two blocks: 3.063386001572265625
+	assert_eq!(state.predict_spot_price(2), Ok(30_633_860));
+
+	let invalid = PricingState { queue_size: 11, ..state };
+	assert_eq!(invalid.predict_spot_price(2), Err(PricingError::QueueSizeLargerThanCapacity));
+	// No prediction needed, so the queue isn't checked.
+	assert_eq!(invalid.predict_spot_price(0), Ok(10_000_000));
+}
+
+#[test]
+fn placer_fallback_rotates_authorities() {
+	let fallback = PlacerFallback { rotate_after: 5 };
+	let authorities = [1u8, 2, 3];
+
+	assert_eq!(fallback.eligible(2, &authorities, 0), EligiblePlacer::One(2));
+	assert_eq!(fallback.eligible(2, &authorities, 4), EligiblePlacer::One(2));
+	// The next authority takes over, wrapping around the set.
+	assert_eq!(fallback.eligible(2, &authorities, 5), EligiblePlacer::One(3));
+	assert_eq!(fallback.eligible(2, &authorities, 10), EligiblePlacer::One(1));
+	// Every authority had its turn.
+	assert_eq!(fallback.eligible(2, &authorities, 15), EligiblePlacer::Any);
+}
+
+#[test]
+fn head_tracker_counts_idle_blocks() {
+	let tracker = HeadTracker::default();
+	let head = H256::repeat_byte(1);
+
+	assert_eq!(tracker.idle_for(head, 10), 0);
+	assert_eq!(tracker.idle_for(head, 15), 5);
+	// The parachain made progress.
+	assert_eq!(tracker.idle_for(H256::repeat_byte(2), 16), 0);
+	assert_eq!(tracker.idle_for(H256::repeat_byte(2), 18), 2);
+}
+
+#[test]
+fn aura_election_elects_parent_author() {
+	let authorities = [1u8, 2, 3];
+
+	assert_eq!(AuraElection::elect(&authorities, &para_header(Some(7)), 0), Some(2));
+	// Relay chain progress doesn't affect the election.
+	assert_eq!(AuraElection::elect(&authorities, &para_header(Some(7)), 100), Some(2));
+	assert_eq!(AuraElection::elect(&authorities, &para_header(Some(9)), 0), Some(1));
+	// Without a pre-runtime digest the author can't be found.
+	assert_eq!(AuraElection::elect(&authorities, &para_header(None), 0), None);
+	assert_eq!(AuraElection::elect(&[] as &[u8], &para_header(Some(7)), 0), None);
+}
+
+#[test]
+fn round_robin_rotates_with_relay_height() {
+	let authorities = [1u8, 2, 3];
+	let header = para_header(Some(7));
+
+	assert_eq!(RoundRobin::elect(&authorities, &header, 0), Some(1));
+	assert_eq!(RoundRobin::elect(&authorities, &header, 1), Some(2));
+	assert_eq!(RoundRobin::elect(&authorities, &header, 5), Some(3));
+	assert_eq!(RoundRobin::elect(&[] as &[u8], &header, 5), None);
+}
+
+#[test]
+fn signer_uses_payment_key_of_elected_authority() {
+	let keystore: KeystorePtr = Arc::new(MemoryKeystore::new());
+	let authority_a = keystore.sr25519_generate_new(AURA, None).unwrap();
+	let authority_b = keystore.sr25519_generate_new(AURA, None).unwrap();
+	let payer_a = keystore.sr25519_generate_new(KEY_TYPE, None).unwrap();
+	let payer_b = keystore.sr25519_generate_new(KEY_TYPE, None).unwrap();
+	let payment_accounts = PaymentAccounts::new([
+		(authority_a.to_raw_vec(), payer_a),
+		(authority_b.to_raw_vec(), payer_b),
+	]);
+
+	let signer =
+		PaymentSigner::for_authority(keystore.clone(), &payment_accounts, authority_b.as_slice())
+			.unwrap();
+	assert_eq!(signer.public(), payer_b);
+	assert_eq!(signer.account_id(), AccountId32(payer_b.0));
+
+	let MultiSignature::Sr25519(signature) = signer.sign(b"order").unwrap() else {
+		panic!("Expected an sr25519 signature")
+	};
+	let signature = sr25519::Signature::from_raw(signature);
+	assert!(sr25519::Pair::verify(&signature, b"order", &payer_b));
+	assert!(!sr25519::Pair::verify(&signature, b"order", &payer_a));
+}
+
+#[test]
+fn signer_requires_payment_key() {
+	let keystore: KeystorePtr = Arc::new(MemoryKeystore::new());
+	let authority = keystore.sr25519_generate_new(AURA, Some("//Alice")).unwrap();
+
+	// The Aura key is never used for payments.
+	assert!(matches!(
+		PaymentSigner::for_authority(keystore.clone(), &Default::default(), authority.as_slice()),
+		Err(SigningError::MissingKey(key)) if key == authority
+	));
+	assert!(matches!(
+		PaymentSigner::for_authority(keystore.clone(), &Default::default(), &[0u8; 4]),
+		Err(SigningError::NoPaymentAccount)
+	));
+
+	// Unless it is explicitly inserted as a payment key.
+	keystore.sr25519_generate_new(KEY_TYPE, Some("//Alice")).unwrap();
+	let signer =
+		PaymentSigner::for_authority(keystore, &Default::default(), authority.as_slice()).unwrap();
+	assert_eq!(signer.public(), authority);
+}
+
+#[test]
+fn spend_tracker_without_budget_is_unlimited() {
+	let tracker = SpendTracker::new(None);
+	tracker.record(1, 1_000);
+
+	assert!(tracker.can_spend(1, u128::MAX));
+	assert_eq!(tracker.spent(1), 0);
+}
+
+#[test]
+fn spend_tracker_enforces_budget() {
+	let tracker = SpendTracker::new(Some(SpendBudget { max_spend: 100, window: 10 }));
+	assert!(tracker.can_spend(1, 100));
+	assert!(!tracker.can_spend(1, 101));
+
+	tracker.record(1, 60);
+	tracker.record(5, 30);
+	assert_eq!(tracker.spent(5), 90);
+	assert!(tracker.can_spend(5, 10));
+	assert!(!tracker.can_spend(5, 11));
+}
+
+#[test]
+fn spend_tracker_forgets_spends_outside_window() {
+	let tracker = SpendTracker::new(Some(SpendBudget { max_spend: 100, window: 10 }));
+	tracker.record(1, 60);
+	tracker.record(5, 30);
+
+	// The window ending at relay block 11 starts after relay block 1.
+	assert_eq!(tracker.spent(10), 90);
+	assert_eq!(tracker.spent(11), 30);
+	assert!(tracker.can_spend(11, 70));
+	assert_eq!(tracker.spent(15), 0);
+}
+
+#[test]
+fn percentage_works() {
+	assert_eq!(percentage(0, 200), 0);
+	assert_eq!(percentage(50, 200), 25);
+	// Capped at 100.
+	assert_eq!(percentage(500, 200), 100);
+	// A zero threshold is always met.
+	assert_eq!(percentage(0, 0), 100);
+}
+
+#[test]
+fn decision_progress_uses_measurements() {
+	let decision = Decision::skip(ReasonCode::Fees, "pending fees 50 of threshold 200")
+		.measured(ReasonCode::Fees, 50, 200)
+		.measured(ReasonCode::IdleTime, 30, 10);
+
+	assert_eq!(decision.progress(ReasonCode::Fees), Some(25));
+	assert_eq!(decision.progress(ReasonCode::IdleTime), Some(100));
+	// Nothing measured for criteria whose threshold isn't set.
+	assert_eq!(decision.progress(ReasonCode::TransactionCount), None);
+}
+
+#[test]
+fn criteria_decision_metrics_use_reason_codes() {
+	let registry = prometheus_endpoint::Registry::new();
+	let metrics = Metrics::register(&registry).unwrap();
+	let gauge = |name: &str| -> Vec<(String, u64)> {
+		let family = registry.gather().into_iter().find(|f| f.get_name() == name).unwrap();
+		family
+			.get_metric()
+			.iter()
+			.map(|m| (m.get_label()[0].get_value().to_string(), m.get_gauge().get_value() as u64))
+			.collect()
+	};
+
+	let decision = Decision::skip(ReasonCode::TransactionCount, "2 of 5 ready transactions")
+		.measured(ReasonCode::TransactionCount, 2, 5);
+	metrics.criteria_decision(&decision);
+	assert_eq!(gauge("on_demand_criteria_decision"), vec![("transaction_count".into(), 0)]);
+	assert_eq!(gauge("on_demand_criteria_value"), vec![("transaction_count".into(), 2)]);
+	assert_eq!(gauge("on_demand_criteria_threshold"), vec![("transaction_count".into(), 5)]);
+
+	// Only the latest decision is kept, regardless of the numbers in the reason.
+	metrics.criteria_decision(&Decision::place(ReasonCode::KeepAlive, "no parachain block"));
+	assert_eq!(gauge("on_demand_criteria_decision"), vec![("keep_alive".into(), 1)]);
+}
+
+fn settings(slot_width: u32, threshold_parameter: u128) -> Settings<u128, u128> {
+	Settings {
+		slot_width,
+		threshold_parameter,
+		max_order_price: None,
+		max_idle_relay_blocks: None,
+		inbound_message_threshold: None,
+		thresholds: Vec::new(),
+	}
+}
+
+#[test]
+fn settings_cache_applies_changes() {
+	let cache = SettingsCache::<u128, u128>::default();
+
+	// Nothing to apply the change to.
+	assert!(!cache.apply(SettingsChange::SlotWidth(4)));
+	assert_eq!(cache.get(), None);
+
+	cache.set(settings(2, 1_000));
+	assert!(cache.apply(SettingsChange::SlotWidth(4)));
+	assert!(cache.apply(SettingsChange::ThresholdParameter(2_000)));
+	assert_eq!(cache.get(), Some(settings(4, 2_000)));
+
+	// Other settings have to be re-fetched.
+	assert!(!cache.apply(SettingsChange::Other));
+	assert_eq!(cache.get(), Some(settings(4, 2_000)));
+
+	cache.set(settings(6, 3_000));
+	assert_eq!(cache.get(), Some(settings(6, 3_000)));
+}
+
+#[test]
+fn settings_cache_applies_threshold_changes() {
+	let cache = SettingsCache::<u128, u128>::default();
+	cache.set(settings(2, 1_000));
+
+	assert!(cache.apply(SettingsChange::Threshold(ThresholdKind::Price, Some(500))));
+	assert!(cache.apply(SettingsChange::Threshold(ThresholdKind::Fees, Some(1_000))));
+	assert!(cache.apply(SettingsChange::Threshold(ThresholdKind::TransactionCount, Some(5))));
+	// The thresholds are kept ordered by kind, as in the runtime.
+	assert_eq!(
+		cache.get().unwrap().thresholds,
+		vec![
+			(ThresholdKind::Fees, 1_000),
+			(ThresholdKind::TransactionCount, 5),
+			(ThresholdKind::Price, 500)
+		]
+	);
+
+	assert!(cache.apply(SettingsChange::Threshold(ThresholdKind::Price, Some(600))));
+	assert!(cache.apply(SettingsChange::Threshold(ThresholdKind::TransactionCount, None)));
+	// Removing a threshold which isn't set changes nothing.
+	assert!(cache.apply(SettingsChange::Threshold(ThresholdKind::IdleTime, None)));
+	assert_eq!(
+		cache.get().unwrap().thresholds,
+		vec![(ThresholdKind::Fees, 1_000), (ThresholdKind::Price, 600)]
+	);
+	assert_eq!(cache.get().unwrap().threshold(ThresholdKind::Price), Some(&600));
+	assert_eq!(cache.get().unwrap().threshold(ThresholdKind::TransactionCount), None);
+}
+
+/// Mirrors of the relay chain `assigner_coretime` types, which aren't public.
+mod assigner_coretime {
+	use codec::Encode;
+	use scale_info::TypeInfo;
+
+	#[derive(Encode, TypeInfo)]
+	pub struct PartsOf57600(pub u16);
+
+	#[derive(Encode, TypeInfo)]
+	pub enum CoreAssignment {
+		Idle,
+		Pool,
+		Task(u32),
+	}
+
+	#[derive(Encode, TypeInfo)]
+	pub struct AssignmentState {
+		pub ratio: PartsOf57600,
+		pub remaining: PartsOf57600,
+	}
+
+	#[derive(Encode, TypeInfo)]
+	pub struct WorkState<N> {
+		pub assignments: Vec<(CoreAssignment, AssignmentState)>,
+		pub end_hint: Option<N>,
+		pub pos: u16,
+		pub step: PartsOf57600,
+	}
+
+	#[derive(Encode, TypeInfo)]
+	pub struct QueueDescriptor<N> {
+		pub first: N,
+		pub last: N,
+	}
+
+	#[derive(Encode, TypeInfo)]
+	pub struct CoreDescriptor<N> {
+		pub queue: Option<QueueDescriptor<N>>,
+		pub current_work: Option<WorkState<N>>,
+	}
+}
+
+/// Encodes the descriptor and decodes it the way the relay chain storage is decoded.
+fn decode_descriptor(
+	descriptor: assigner_coretime::CoreDescriptor<u32>,
+) -> scale_value::Value<u32> {
+	let mut registry = Registry::new();
+	let ty = registry
+		.register_type(&MetaType::new::<assigner_coretime::CoreDescriptor<u32>>())
+		.id;
+	let types: PortableRegistry = registry.into();
+
+	scale_value::scale::decode_as_type(&mut &descriptor.encode()[..], ty, &types).unwrap()
+}
+
+#[test]
+fn pool_core_sums_pool_assignments() {
+	use assigner_coretime::*;
+
+	let assignment = |assignment, ratio| {
+		(assignment, AssignmentState { ratio: PartsOf57600(ratio), remaining: PartsOf57600(ratio) })
+	};
+	let descriptor = decode_descriptor(CoreDescriptor {
+		queue: Some(QueueDescriptor { first: 10, last: 20 }),
+		current_work: Some(WorkState {
+			assignments: vec![
+				assignment(CoreAssignment::Pool, 14_400),
+				assignment(CoreAssignment::Task(2000), 28_800),
+				assignment(CoreAssignment::Pool, 14_400),
+			],
+			end_hint: None,
+			pos: 0,
+			step: PartsOf57600(14_400),
+		}),
+	});
+
+	assert_eq!(
+		pool_core(CoreIndex(3), &descriptor),
+		Some(PoolCore {
+			core: CoreIndex(3),
+			parts: 28_800,
+			queue: Some(CoreQueue { first: 10, last: 20 })
+		})
+	);
+}
+
+#[test]
+fn pool_core_ignores_cores_without_pool_assignments() {
+	use assigner_coretime::*;
+
+	let idle = decode_descriptor(CoreDescriptor { queue: None, current_work: None });
+	assert_eq!(pool_core(CoreIndex(0), &idle), None);
+
+	let task = decode_descriptor(CoreDescriptor {
+		queue: None,
+		current_work: Some(WorkState {
+			assignments: vec![(
+				CoreAssignment::Task(2000),
+				AssignmentState { ratio: PartsOf57600(57_600), remaining: PartsOf57600(57_600) },
+			)],
+			end_hint: None,
+			pos: 0,
+			step: PartsOf57600(57_600),
+		}),
+	});
+	assert_eq!(pool_core(CoreIndex(0), &task), None);
+}
+
+#[test]
+fn backed_candidates_match_order_core_and_block() {
+	let records = OrderRecords::default();
+	let (order, placed_in, backed_in) =
+		(H256::repeat_byte(1), H256::repeat_byte(2), H256::repeat_byte(3));
+
+	records.submitted(order, 10, 100, vec![2, 3]);
+	assert!(records.awaiting_inclusion(10));
+	// Candidates backed before the order got placed aren't ours.
+	assert_eq!(records.backed(backed_in, 11, 2), None);
+
+	records.placed(order, placed_in, 11, 90);
+	assert!(!records.awaiting_inclusion(11));
+	// Neither are candidates backed in the block containing the order, or on other cores.
+	assert_eq!(records.backed(backed_in, 11, 2), None);
+	assert_eq!(records.backed(backed_in, 12, 1), None);
+
+	let record = records.backed(backed_in, 12, 3).unwrap();
+	assert_eq!(record.tx_hash, order);
+	assert_eq!(record.status, OrderStatus::Backed { relay_block: backed_in, core: 3 });
+	// Each order is backed only once.
+	assert_eq!(records.backed(backed_in, 13, 3), None);
+}
+
+#[test]
+fn lost_orders_expire() {
+	let records = OrderRecords::default();
+	let order = H256::repeat_byte(1);
+
+	records.submitted(order, 10, 100, vec![2]);
+	assert!(records.awaiting_inclusion(10 + INCLUSION_TIMEOUT - 1));
+
+	// Ordering resumes once the order wasn't included in time.
+	assert!(!records.awaiting_inclusion(10 + INCLUSION_TIMEOUT));
+	records.submitted(H256::repeat_byte(2), 10 + INCLUSION_TIMEOUT, 100, vec![2]);
+	assert!(records.awaiting_inclusion(10 + INCLUSION_TIMEOUT));
+
+	// A late inclusion is still recorded.
+	records.placed(order, H256::repeat_byte(3), 25, 90);
+	assert!(records.backed(H256::repeat_byte(4), 26, 2).is_some_and(|r| r.tx_hash == order));
+}
+
+sp_runtime::impl_opaque_keys! {
+	pub struct TestSessionKeys {
+		pub aura: AuraId,
+	}
+}
+
+/// Storage key of a `pallet-collator-selection` or `pallet-session` entry.
+fn collator_storage_key(pallet: &str, entry: &str, account: Option<u64>) -> Vec<u8> {
+	let mut key = [twox_128(pallet.as_bytes()), twox_128(entry.as_bytes())].concat();
+	if let Some(account) = account {
+		key.extend(twox_64(&account.encode()));
+		key.extend(account.encode());
+	}
+	key
+}
+
+#[test]
+fn collator_selection_authorities_are_session_keys() {
+	let keys: Vec<AuraId> =
+		(0..3).map(|i| sr25519::Pair::from_seed(&[i; 32]).public().into()).collect();
+
+	let mut storage = BTreeMap::new();
+	storage.insert(
+		collator_storage_key("CollatorSelection", "Invulnerables", None),
+		vec![1u64].encode(),
+	);
+	// The deposits are of the parachain balance type, which is `u64` here.
+	storage.insert(
+		collator_storage_key("CollatorSelection", "CandidateList", None),
+		vec![(2u64, 500u64), (3u64, 400u64), (4u64, 300u64)].encode(),
+	);
+	// Collator 2 has no session keys.
+	for (account, key) in [(1u64, &keys[0]), (3, &keys[1]), (4, &keys[2])] {
+		storage.insert(
+			collator_storage_key("Session", "NextKeys", Some(account)),
+			TestSessionKeys { aura: key.clone() }.encode(),
+		);
+	}
+
+	let authorities = collator_selection_authorities::<u64, u64, TestSessionKeys, AuraId>(|key| {
+		Ok(storage.get(&key).cloned())
+	})
+	.unwrap();
+	assert_eq!(authorities, keys);
+
+	// Without collators there are no authorities.
+	let authorities =
+		collator_selection_authorities::<u64, u64, TestSessionKeys, AuraId>(|_| Ok(None)).unwrap();
+	assert!(authorities.is_empty());
+}
+
+#[test]
+fn order_placer_is_elected_from_para_header() {
+	let keys: Vec<AuraId> =
+		(0..3).map(|i| sr25519::Pair::from_seed(&[i; 32]).public().into()).collect();
+	let elect = |authorities: Result<Vec<AuraId>, String>, slot, relay_height| {
+		futures::executor::block_on(elect_order_placer::<_, _, AuraElection>(
+			authorities,
+			para_header(slot),
+			relay_height,
+		))
+	};
+
+	// The author of the parent block is elected.
+	assert_eq!(elect(Ok(keys.clone()), Some(4), 10).unwrap(), keys[1]);
+	assert_eq!(elect(Ok(keys.clone()), Some(5), 10).unwrap(), keys[2]);
+	// Headers without an Aura digest can't be used for the election.
+	assert!(elect(Ok(keys.clone()), None, 10).is_err());
+	assert!(elect(Ok(Vec::new()), Some(4), 10).is_err());
+	assert!(elect(Err("no authorities".into()), Some(4), 10).is_err());
+
+	// With collator selection, the placer rotates every relay chain block.
+	let round_robin = |relay_height| {
+		futures::executor::block_on(elect_order_placer::<_, _, RoundRobin>(
+			Ok(keys.clone()),
+			para_header(None),
+			relay_height,
+		))
+		.unwrap()
+	};
+	assert_eq!(round_robin(10), keys[1]);
+	assert_eq!(round_robin(11), keys[2]);
+	assert_eq!(round_robin(12), keys[0]);
+}